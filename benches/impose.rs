@@ -0,0 +1,108 @@
+//! Layout benchmarks. These need a nightly compiler:
+//! `cargo +nightly bench --features nightly`
+
+#![feature(test)]
+
+extern crate sktablelayout;
+extern crate test;
+
+use sktablelayout::*;
+use test::Bencher;
+
+/// Builds a grid of uniformly sized cells, where every other column
+/// expands to soak up extra space.
+fn grid(rows: usize, cols: usize) -> TableLayout {
+    let mut engine = TableLayout::new();
+    for _row in 0..rows {
+        for col in 0..cols {
+            let cell = CellProperties::new()
+                .minimum_size(Size{width: 8.0, height: 8.0})
+                .preferred_size(Size{width: 16.0, height: 16.0});
+            engine.with_cell(if col % 2 == 0 { cell.expand().fill() } else { cell });
+        }
+        engine.with_row();
+    }
+    engine
+}
+
+#[bench]
+fn impose2x3(b: &mut Bencher) {
+    // We only test the speed of layout calculation here, not
+    // the overhead of communicating the layout back to the client.
+    let mut engine = TableLayout::new();
+    engine.with_cell(CellProperties::new()
+                    .anchor_right()
+                    .anchor_bottom()
+                    .preferred_size(Size{width: 64.0, height: 64.0}));
+    engine.with_cell(CellProperties::new()
+                    .anchor_top()
+                    .anchor_left()
+                    .expand_horizontal()
+                    .preferred_size(Size{width: 64.0, height: 64.0}));
+    engine.with_cell(CellProperties::new()
+                    .anchor_right()
+                    .expand_horizontal()
+                    .fill_horizontal()
+                    .preferred_size(Size{width: 64.0, height: 64.0}));
+    engine.with_row();
+    engine.with_cell(CellProperties::new()
+                    .colspan(3)
+                    .expand_vertical()
+                    .anchor_bottom()
+                    .fill_horizontal()
+                    .preferred_size(Size{width: 64.0, height: 64.0}));
+    b.iter(|| engine.impose(test::black_box(320.0), test::black_box(240.0)).unwrap())
+}
+
+#[bench]
+fn impose_large_grid(b: &mut Bencher) {
+    // 64x64 cells preferring 16 units each, given plenty of room
+    let mut engine = grid(64, 64);
+    b.iter(|| engine.impose(test::black_box(2048.0), test::black_box(2048.0)).unwrap())
+}
+
+#[bench]
+fn shrink_large_grid(b: &mut Bencher) {
+    // same grid, but squeezed between its minimum and preferred sizes
+    let mut engine = grid(64, 64);
+    b.iter(|| engine.impose(test::black_box(768.0), test::black_box(768.0)).unwrap())
+}
+
+#[bench]
+fn overflow_large_grid(b: &mut Bencher) {
+    // same grid, but too small to fit even at minimum size
+    let mut engine = grid(64, 64);
+    b.iter(|| engine.impose(test::black_box(256.0), test::black_box(256.0)).is_err())
+}
+
+#[bench]
+fn impose_colspans(b: &mut Bencher) {
+    // rows alternate between one cell spanning all columns, and
+    // cells spanning one, two and three columns
+    let mut engine = TableLayout::new();
+    for row in 0..32 {
+        if row % 2 == 0 {
+            engine.with_cell(CellProperties::new()
+                            .colspan(12)
+                            .expand_horizontal()
+                            .fill_horizontal()
+                            .preferred_size(Size{width: 240.0, height: 16.0}));
+        } else {
+            for span in &[1, 2, 3, 3, 2, 1] {
+                engine.with_cell(CellProperties::new()
+                                .colspan(*span)
+                                .expand_horizontal()
+                                .preferred_size(Size{width: 16.0 * *span as f32, height: 16.0}));
+            }
+        }
+        engine.with_row();
+    }
+    b.iter(|| engine.impose(test::black_box(1024.0), test::black_box(768.0)).unwrap())
+}
+
+#[bench]
+fn solve_large_grid(b: &mut Bencher) {
+    // placements without the callbacks
+    let engine = grid(64, 64);
+    b.iter(|| engine.solve(test::black_box(2048.0), test::black_box(2048.0)).unwrap())
+}
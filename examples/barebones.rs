@@ -27,6 +27,8 @@ fn main() {
                     .anchor_bottom()
                     .fill_horizontal()
                     .preferred_size(Size{width: 64.0, height: 64.0}));
-    engine.impose(320.0, 240.0);
+    if let Err(e) = engine.impose(320.0, 240.0) {
+        eprintln!("{}", e);
+    }
 }
 
@@ -0,0 +1,308 @@
+//! Builds tables from compact text descriptions, in the spirit of
+//! Esoteric Software's tablelayout.
+//!
+//! A description is a list of cells. Each cell starts with its name in
+//! square brackets, followed by the constraints which apply to it:
+//!
+//! ```text
+//! [label] right | [field] expandx fillx
+//! ---
+//! [ok] colspan:2 right pad:4
+//! ```
+//!
+//! `---` or `row` starts a new row, and `|` may separate cells for
+//! readability. Names may be left empty (`[]`) for cells which nobody
+//! needs to find again; named cells have callbacks bound to them with
+//! `TableLayout::bind`.
+//!
+//! The constraints are:
+//!
+//! - `expand`, `expandx`, `expandy`: expand both ways, horizontally or vertically.
+//! - `fill`, `fillx`, `filly`: fill both ways, horizontally or vertically.
+//! - `left`, `right`, `top`, `bottom`, `center`, `centerx`, `centery`: anchors.
+//! - `uniform`: shares its size with every other uniform cell.
+//! - `size:W,H`, `min:W,H`, `max:W,H`: preferred, minimum and maximum
+//!   size. A single number is used for both the width and height.
+//! - `pad:T,L,B,R`: padding. A single number pads every side, and two
+//!   numbers pad the top and bottom, then the left and right.
+//! - `colspan:N`, `rowspan:N`: how many columns and rows the cell covers.
+//! - `weight:N`: expansion weight.
+
+use std::error::Error;
+use std::fmt;
+
+use {CellProperties, LayoutOp, Rectangle, Scalar, Size, TableLayout};
+
+/// Describes what was wrong with a description, and where.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParseError {
+    /// Line the problem was found on, counting from one.
+    pub line:    usize,
+    /// Column the problem was found at, counting characters from one.
+    pub column:  usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+/// Piece of a description, along with where it began.
+struct Token<'a> {
+    line:   usize,
+    column: usize,
+    text:   &'a str,
+}
+
+impl<'a> Token<'a> {
+    fn error(&self, message: String) -> ParseError {
+        ParseError{line: self.line, column: self.column, message}
+    }
+}
+
+/// Splits a description into cell names, separators and constraints.
+fn tokenize(source: &str) -> Result<Vec<Token<'_>>, ParseError> {
+    let mut tokens = Vec::new();
+    for (l, text) in source.lines().enumerate() {
+        let mut chars = text.char_indices().peekable();
+        let mut column = 0;
+        while let Some((start, c)) = chars.next() {
+            column += 1;
+            if c.is_whitespace() {
+                continue
+            }
+
+            let begun = column;
+            let mut end = start + c.len_utf8();
+            if c == '[' {
+                // names run until the closing bracket
+                loop {
+                    match chars.next() {
+                        Some((i, c)) => {
+                            column += 1;
+                            end = i + c.len_utf8();
+                            if c == ']' {
+                                break
+                            }
+                        }
+                        None => return Err(ParseError{
+                            line: l + 1,
+                            column: begun,
+                            message: "cell name is missing its closing `]`".to_string(),
+                        }),
+                    }
+                }
+            } else if c != '|' {
+                // constraints run until whitespace, or something else begins
+                while let Some(&(i, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '[' || c == '|' {
+                        break
+                    }
+                    chars.next();
+                    column += 1;
+                    end = i + c.len_utf8();
+                }
+            }
+
+            tokens.push(Token{line: l + 1, column: begun, text: &text[start..end]});
+        }
+    }
+    Ok(tokens)
+}
+
+/// Reads between `min` and `max` comma separated numbers.
+fn numbers<T: Scalar>(token: &Token, key: &str, value: &str, min: usize, max: usize) -> Result<Vec<T>, ParseError> {
+    let mut out = Vec::new();
+    for part in value.split(',') {
+        match part.parse::<f64>() {
+            Ok(n) => out.push(T::from_f64(n)),
+            Err(_) => return Err(token.error(format!("`{}` expects numbers, not `{}`", key, part))),
+        }
+    }
+    if out.len() < min || out.len() > max {
+        let wanted = if min == max { format!("{}", min) } else { format!("{} to {}", min, max) };
+        return Err(token.error(format!("`{}` expects {} numbers, not {}", key, wanted, out.len())))
+    }
+    Ok(out)
+}
+
+/// Reads a size, where a single number is used for both the width and height.
+fn size<T: Scalar>(token: &Token, key: &str, value: &str) -> Result<Size<T>, ParseError> {
+    let n = numbers(token, key, value, 1, 2)?;
+    Ok(Size{width: n[0], height: *n.last().unwrap()})
+}
+
+/// Reads a whole, positive number of rows or columns.
+fn span(token: &Token, key: &str, value: &str) -> Result<usize, ParseError> {
+    match value.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(token.error(format!("`{}` expects a whole number above zero, not `{}`", key, value))),
+    }
+}
+
+/// Applies a single constraint to a cell.
+fn constrain<T: Scalar>(cell: CellProperties<T>, token: &Token) -> Result<CellProperties<T>, ParseError> {
+    let (key, value) = match token.text.find(':') {
+        Some(i) => (&token.text[..i], Some(&token.text[i+1..])),
+        None => (token.text, None),
+    };
+
+    let cell = match (key, value) {
+        ("expand", None) => cell.expand(),
+        ("expandx", None) => cell.expand_horizontal(),
+        ("expandy", None) => cell.expand_vertical(),
+        ("fill", None) => cell.fill(),
+        ("fillx", None) => cell.fill_horizontal(),
+        ("filly", None) => cell.fill_vertical(),
+        ("left", None) => cell.anchor_left(),
+        ("right", None) => cell.anchor_right(),
+        ("top", None) => cell.anchor_top(),
+        ("bottom", None) => cell.anchor_bottom(),
+        ("center", None) => cell.anchor_center(),
+        ("centerx", None) => cell.anchor_horizontal_center(),
+        ("centery", None) => cell.anchor_vertical_center(),
+        ("uniform", None) => cell.uniform(),
+        ("size", Some(v)) => cell.preferred_size(size(token, key, v)?),
+        ("min", Some(v)) => cell.minimum_size(size(token, key, v)?),
+        ("max", Some(v)) => cell.maximum_size(size(token, key, v)?),
+        ("pad", Some(v)) => {
+            let n: Vec<T> = numbers(token, key, v, 1, 4)?;
+            let (top, left, bottom, right) = match n.len() {
+                1 => (n[0], n[0], n[0], n[0]),
+                2 => (n[0], n[1], n[0], n[1]),
+                3 => return Err(token.error("`pad` expects 1, 2 or 4 numbers, not 3".to_string())),
+                _ => (n[0], n[1], n[2], n[3]),
+            };
+            cell.padding(Rectangle{top, left, bottom, right})
+        }
+        ("colspan", Some(v)) => cell.colspan(span(token, key, v)?),
+        ("rowspan", Some(v)) => cell.rowspan(span(token, key, v)?),
+        ("weight", Some(v)) => match v.parse::<f32>() {
+            Ok(w) => cell.expand_weight(w),
+            Err(_) => return Err(token.error(format!("`weight` expects a number, not `{}`", v))),
+        },
+        ("expand", Some(_)) | ("expandx", Some(_)) | ("expandy", Some(_)) | ("fill", Some(_)) |
+        ("fillx", Some(_)) | ("filly", Some(_)) | ("left", Some(_)) | ("right", Some(_)) |
+        ("top", Some(_)) | ("bottom", Some(_)) | ("center", Some(_)) | ("centerx", Some(_)) |
+        ("centery", Some(_)) | ("uniform", Some(_)) =>
+            return Err(token.error(format!("`{}` does not take a value", key))),
+        ("size", None) | ("min", None) | ("max", None) | ("pad", None) |
+        ("colspan", None) | ("rowspan", None) | ("weight", None) =>
+            return Err(token.error(format!("`{}` needs a value, as in `{}:...`", key, key))),
+        _ => return Err(token.error(format!("unknown constraint `{}`", key))),
+    };
+    Ok(cell)
+}
+
+/// Adds the cells and rows described by `source` to the end of `table`.
+/// Nothing is added if the description has a mistake in it; the error
+/// says what the mistake was and where it was found.
+pub fn parse<T: Scalar>(table: &mut TableLayout<T>, source: &str) -> Result<(), ParseError> {
+    let mut ops: Vec<LayoutOp<T>> = Vec::new();
+    let mut cell: Option<CellProperties<T>> = None;
+
+    for token in tokenize(source)? {
+        if token.text.starts_with('[') {
+            if let Some(done) = cell.take() {
+                ops.push(LayoutOp::Cell(done));
+            }
+            let name = token.text[1..token.text.len()-1].trim();
+            let mut next = CellProperties::default();
+            if !name.is_empty() {
+                next = next.id(name);
+            }
+            cell = Some(next);
+        } else if token.text == "|" {
+            if let Some(done) = cell.take() {
+                ops.push(LayoutOp::Cell(done));
+            }
+        } else if token.text == "---" || token.text == "row" {
+            if let Some(done) = cell.take() {
+                ops.push(LayoutOp::Cell(done));
+            }
+            ops.push(LayoutOp::Row);
+        } else {
+            match cell.take() {
+                Some(c) => cell = Some(constrain(c, &token)?),
+                None => return Err(token.error(format!("`{}` must follow a cell, as in `[name] {}`", token.text, token.text))),
+            }
+        }
+    }
+    if let Some(done) = cell.take() {
+        ops.push(LayoutOp::Cell(done));
+    }
+
+    // Only touch the table once the whole description has been read.
+    for op in ops {
+        match op {
+            LayoutOp::Cell(cp) => { table.with_cell(cp); }
+            LayoutOp::Row => { table.with_row(); }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ::*;
+    use dsl::*;
+
+    #[test]
+    fn parsed_layout() {
+        let mut engine = TableLayout::new();
+        parse(&mut engine, "
+            [label] right size:40,16 | [field] expandx fillx size:100,16 pad:0,4
+            ---
+            [] colspan:2 min:8 rowspan:1 weight:2.5 row
+            [ok] center uniform max:64,32
+        ").unwrap();
+
+        assert_eq!(engine.get_rows_cols(), (3, 2));
+        assert_eq!(engine.row, 2);
+        let cells: Vec<&CellProperties> = engine.opcodes.iter()
+            .filter_map(|op| match op { LayoutOp::Cell(cp) => Some(cp), LayoutOp::Row => None })
+            .collect();
+        assert_eq!(cells[0].id.as_deref(), Some("label"));
+        assert_eq!(cells[0].flags, CellFlags::AnchorRight);
+        assert_eq!(cells[1].flags, CellFlags::ExpandHorizontal | CellFlags::FillHorizontal);
+        assert_eq!(cells[1].padding.left, 4.0);
+        assert_eq!(cells[1].padding.top, 0.0);
+        assert_eq!(cells[2].id, None);
+        assert_eq!(cells[2].colspan, 2);
+        assert_eq!(cells[2].size.minimum.height, 8.0);
+        assert_eq!(cells[2].expand_weight, 2.5);
+        assert_eq!(cells[3].size.maximum.width, 64.0);
+
+        // callbacks are bound to named cells afterwards
+        assert!(engine.bind("field", Box::new(|x, _, w, _| {
+            assert_eq!(x, 40.0 + 4.0);
+            assert_eq!(w, 160.0 - 8.0);
+        })));
+        engine.impose(200.0, 100.0).unwrap();
+    }
+
+    #[test]
+    fn parse_errors() {
+        let mut engine: TableLayout = TableLayout::new();
+        let error = |source: &str| parse(&mut TableLayout::<f32>::default(), source).unwrap_err();
+
+        assert_eq!(error("[a] expandx\n[b] sideways"),
+                   ParseError{line: 2, column: 5, message: "unknown constraint `sideways`".to_string()});
+        assert_eq!(error("  fill [a]"),
+                   ParseError{line: 1, column: 3, message: "`fill` must follow a cell, as in `[name] fill`".to_string()});
+        assert_eq!(error("[a] size:4,x").message, "`size` expects numbers, not `x`");
+        assert_eq!(error("[a] size:1,2,3").message, "`size` expects 1 to 2 numbers, not 3");
+        assert_eq!(error("[a] colspan:0").message, "`colspan` expects a whole number above zero, not `0`");
+        assert_eq!(error("[a] pad").message, "`pad` needs a value, as in `pad:...`");
+        assert_eq!(error("[a] fill:2").message, "`fill` does not take a value");
+        assert_eq!(error("[a] | [b\n").to_string(), "1:7: cell name is missing its closing `]`");
+
+        // mistakes leave the table alone
+        assert!(parse(&mut engine, "[a] --- [b] bogus").is_err());
+        assert!(engine.opcodes.is_empty());
+    }
+}
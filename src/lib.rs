@@ -1,78 +1,239 @@
+// Cell flags predate the upper case naming convention for bitflags.
+#![allow(non_upper_case_globals)]
 
-#![feature(test)]
 #[macro_use]
 extern crate bitflags;
+#[cfg(feature = "log")]
+#[macro_use]
+extern crate log;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(all(test, feature = "serde"))]
+extern crate serde_json;
 
-use std::f32;
-use std::cmp::max;
+#[macro_use]
+mod macros;
+pub mod dsl;
+pub mod mig;
+pub mod svg;
+
+use std::error::Error;
+use std::fmt;
+use std::cmp::{max, Ordering};
 use std::collections::BTreeMap;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "serde")]
+use serde::ser::SerializeStruct;
+
+/// Numeric type used for every coordinate and size in a layout. This is
+/// `f32` unless asked otherwise, and is also implemented for `f64` and
+/// the signed integers; unsigned integers are left out because the
+/// solver works with negative differences while shrinking.
+pub trait Scalar: Copy + PartialOrd + fmt::Debug + fmt::Display + Sum
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self>
+    + AddAssign + SubAssign
+{
+    fn zero() -> Self;
+    /// Largest representable value; stands in for "no maximum size".
+    fn max_value() -> Self;
+    /// Converts a count of rows, columns or gutters.
+    fn from_usize(n: usize) -> Self;
+    /// Converts a fractional share of space. Integers round toward zero.
+    fn from_f64(n: f64) -> Self;
+    fn to_f64(self) -> f64;
+    /// Adds two values, stopping at `max_value` instead of overflowing.
+    fn saturating_add(self, other: Self) -> Self;
+}
+
+macro_rules! float_scalar {
+    ($t:ident) => {
+        impl Scalar for $t {
+            fn zero() -> Self { 0.0 }
+            fn max_value() -> Self { $t::MAX }
+            fn from_usize(n: usize) -> Self { n as $t }
+            fn from_f64(n: f64) -> Self { n as $t }
+            fn to_f64(self) -> f64 { self as f64 }
+            fn saturating_add(self, other: Self) -> Self {
+                // maximums are often MAX, so keep from running off to infinity
+                $t::min(self + other, $t::MAX)
+            }
+        }
+    }
+}
+
+macro_rules! integer_scalar {
+    ($t:ident) => {
+        impl Scalar for $t {
+            fn zero() -> Self { 0 }
+            fn max_value() -> Self { $t::MAX }
+            fn from_usize(n: usize) -> Self { n as $t }
+            fn from_f64(n: f64) -> Self { n as $t }
+            fn to_f64(self) -> f64 { self as f64 }
+            fn saturating_add(self, other: Self) -> Self { $t::saturating_add(self, other) }
+        }
+    }
+}
+
+float_scalar!(f32);
+float_scalar!(f64);
+integer_scalar!(i16);
+integer_scalar!(i32);
+integer_scalar!(i64);
+integer_scalar!(isize);
+
+/// The larger of two scalars.
+fn larger<T: Scalar>(a: T, b: T) -> T {
+    if b > a { b } else { a }
+}
+
+/// The smaller of two scalars.
+fn smaller<T: Scalar>(a: T, b: T) -> T {
+    if b < a { b } else { a }
+}
 
 /// Rectangle for padding and spacing constraints.
-#[derive(Clone)]
-pub struct Rectangle {
-    pub top:    f32,
-    pub left:   f32,
-    pub bottom: f32,
-    pub right:  f32,
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Rectangle<T = f32> {
+    pub top:    T,
+    pub left:   T,
+    pub bottom: T,
+    pub right:  T,
+}
+
+impl<T: Scalar> Default for Rectangle<T> {
+    fn default() -> Self {
+        Rectangle{top: T::zero(), left: T::zero(), bottom: T::zero(), right: T::zero()}
+    }
+}
+
+impl<T: Scalar> Rectangle<T> {
+    /// Total space taken up along the horizontal axis.
+    pub fn horizontal(&self) -> T {
+        self.left + self.right
+    }
+
+    /// Total space taken up along the vertical axis.
+    pub fn vertical(&self) -> T {
+        self.top + self.bottom
+    }
+}
+
+/// Direction along which space is handed out to columns or rows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Axis {
+    /// Widths, handed out to columns.
+    Horizontal,
+    /// Heights, handed out to rows.
+    Vertical,
 }
 
 /// Individual size constraint for a cell.
 #[derive(Clone)]
-pub struct Size {
-    pub width:  f32,
-    pub height: f32,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Size<T = f32> {
+    pub width:  T,
+    pub height: T,
 }
 
-impl Size {
-    pub fn join_max(a: &Size, b: &Size) -> Self {
+impl<T: Scalar> Size<T> {
+    pub fn join_max(a: &Size<T>, b: &Size<T>) -> Self {
         Size{
-            width: f32::max(a.width, b.width),
-            height: f32::max(a.height, b.height),
+            width: larger(a.width, b.width),
+            height: larger(a.height, b.height),
         }
     }
 
-    pub fn join_min(a: &Size, b: &Size) -> Self {
+    pub fn join_min(a: &Size<T>, b: &Size<T>) -> Self {
         Size{
-            width: f32::min(a.width, b.width),
-            height: f32::min(a.height, b.height),
+            width: smaller(a.width, b.width),
+            height: smaller(a.height, b.height),
         }
     }
 
     /// Divides the width and height by a givin division level. Used when
     /// a size must be spread across multiple table cells.
-    pub fn spread(&self, divisions: f32) -> Self {
+    pub fn spread(&self, divisions: T) -> Self {
         Size{
             width: self.width / divisions,
             height: self.height / divisions,
         }
     }
 
+    /// Returns the width or height, depending on the axis.
+    pub fn get(&self, axis: Axis) -> T {
+        match axis {
+            Axis::Horizontal => self.width,
+            Axis::Vertical => self.height,
+        }
+    }
+
+    /// Returns the width or height for modification, depending on the axis.
+    pub fn get_mut(&mut self, axis: Axis) -> &mut T {
+        match axis {
+            Axis::Horizontal => &mut self.width,
+            Axis::Vertical => &mut self.height,
+        }
+    }
+
     /// Returns whether this size should fit within another size.
-    pub fn within(&self, other: &Size) -> bool {
+    pub fn within(&self, other: &Size<T>) -> bool {
         other.width > self.width && other.height > self.height
     }
+
+    /// Grows the width and height to make room for padding on each side.
+    /// Never grows past `Scalar::max_value`.
+    pub fn inflate(&self, padding: &Rectangle<T>) -> Self {
+        Size{
+            width: self.width.saturating_add(padding.horizontal()),
+            height: self.height.saturating_add(padding.vertical()),
+        }
+    }
+
+    /// Adds another size's width and height to this one. Never grows
+    /// past `Scalar::max_value`.
+    pub fn grow(&self, other: &Size<T>) -> Self {
+        Size{
+            width: self.width.saturating_add(other.width),
+            height: self.height.saturating_add(other.height),
+        }
+    }
+
+    /// Shrinks the width and height to remove padding from each side.
+    /// Never shrinks below zero.
+    pub fn deflate(&self, padding: &Rectangle<T>) -> Self {
+        Size{
+            width: larger(self.width - padding.horizontal(), T::zero()),
+            height: larger(self.height - padding.vertical(), T::zero()),
+        }
+    }
 }
 
 /// Combines the maximum, minimum and preferred sizes for a cell.
 #[derive(Clone)]
-pub struct SizeGrouping {
-    pub minimum:   Size,
-    pub maximum:   Size,
-    pub preferred: Size,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SizeGrouping<T = f32> {
+    pub minimum:   Size<T>,
+    pub maximum:   Size<T>,
+    pub preferred: Size<T>,
 }
 
-impl Default for SizeGrouping {
+impl<T: Scalar> Default for SizeGrouping<T> {
     fn default() -> Self {
         SizeGrouping{
-            minimum:   Size{width: 0.0, height: 0.0},
-            preferred: Size{width: 0.0, height: 0.0},
-            maximum:   Size{width: f32::MAX, height: f32::MAX},
+            minimum:   Size{width: T::zero(), height: T::zero()},
+            preferred: Size{width: T::zero(), height: T::zero()},
+            maximum:   Size{width: T::max_value(), height: T::max_value()},
         }
     }
 }
 
-impl SizeGrouping {
-    pub fn join(a: &SizeGrouping, b: &SizeGrouping) -> SizeGrouping {
+impl<T: Scalar> SizeGrouping<T> {
+    pub fn join(a: &SizeGrouping<T>, b: &SizeGrouping<T>) -> SizeGrouping<T> {
         SizeGrouping{
             minimum:   Size::join_max(&a.minimum,   &b.minimum),
             preferred: Size::join_max(&a.preferred, &b.preferred),
@@ -80,7 +241,27 @@ impl SizeGrouping {
         }
     }
 
-    pub fn spread(&self, divisions: f32) -> SizeGrouping {
+    /// Combines two groupings by taking the largest of each size. Unlike
+    /// `join` this also takes the larger maximum, so that neither input
+    /// is constrained by the other.
+    pub fn join_largest(a: &SizeGrouping<T>, b: &SizeGrouping<T>) -> SizeGrouping<T> {
+        SizeGrouping{
+            minimum:   Size::join_max(&a.minimum,   &b.minimum),
+            preferred: Size::join_max(&a.preferred, &b.preferred),
+            maximum:   Size::join_max(&a.maximum,   &b.maximum),
+        }
+    }
+
+    /// Grows every size in the grouping to make room for padding.
+    pub fn inflate(&self, padding: &Rectangle<T>) -> SizeGrouping<T> {
+        SizeGrouping{
+            minimum:   self.minimum.inflate(padding),
+            preferred: self.preferred.inflate(padding),
+            maximum:   self.maximum.inflate(padding),
+        }
+    }
+
+    pub fn spread(&self, divisions: T) -> SizeGrouping<T> {
         SizeGrouping{
             minimum:   self.minimum.spread(divisions),
             preferred: self.preferred.spread(divisions),
@@ -91,49 +272,67 @@ impl SizeGrouping {
     /// Attempts to fit an `item` of a given size within an `area`, subject
     /// to layout rules specified by `flags`. Returns the X, Y coordinates
     /// as well as width and height of the box fitted to the area.
-    pub fn box_fit(&self, area: &Size, flags: CellFlags) -> (f32, f32, f32, f32) {
+    pub fn box_fit(&self, area: &Size<T>, flags: CellFlags) -> (T, T, T, T) {
         // combine maximum width and area width, depending on if fill has been actiated
         let w = if flags.contains(CellFlags::FillHorizontal) {
-            f32::min(self.maximum.width, area.width)
+            smaller(self.maximum.width, area.width)
         } else {
-            f32::min(self.preferred.width, area.width)
+            smaller(self.preferred.width, area.width)
         };
 
         // combine maximum height and area height, depending on if fill has been actiated
         let h = if flags.contains(CellFlags::FillVertical) {
-            f32::min(self.maximum.height, area.height)
+            smaller(self.maximum.height, area.height)
         } else {
-            f32::min(self.preferred.height, area.height)
+            smaller(self.preferred.height, area.height)
         };
 
         // find horizontal location of output box
-        let x = if flags.contains(CellFlags::AnchorRight) {
-            // take size of the area and remove width, will anchor us to the right side
-            area.width - w
-        } else if flags.contains(CellFlags::AnchorHorizontalCenter) {
-            // tricky because we have to find the midpoint, then adjust by half of width
-            (area.width / 2.0) - (w / 2.0)
-        } else {
-            // AnchorLeft is the same as doing nothing, so we just put this on the left side.
-            0.0
-        };
+        let x = anchor(area.width, w, flags,
+                       CellFlags::AnchorLeft, CellFlags::AnchorRight, CellFlags::AnchorHorizontalCenter);
 
         // find vertical location of output box
-        let y = if flags.contains(CellFlags::AnchorBottom) {
-            // take size of the area and remove height, will anchor us to the top side
-            area.height - h
-        } else if flags.contains(CellFlags::AnchorHorizontalCenter) {
-            // tricky because we have to find the midpoint, then adjust by half of height
-            (area.height / 2.0) - (h / 2.0)
-        } else {
-            // AnchorTop is the same as doing nothing, so we just put this on the top side.
-            0.0
-        };
+        let y = anchor(area.height, h, flags,
+                       CellFlags::AnchorTop, CellFlags::AnchorBottom, CellFlags::AnchorVerticalCenter);
 
         (x, y, w, h)
     }
 }
 
+/// Copies the width and height of `from` into `to`, each if it was set
+/// explicitly or differs from the `default` size.
+fn overlay_size<T: Scalar>(to: &mut Size<T>, from: &Size<T>, default: &Size<T>, width: bool, height: bool) {
+    if width || from.width != default.width {
+        to.width = from.width;
+    }
+    if height || from.height != default.height {
+        to.height = from.height;
+    }
+}
+
+/// Keeps an expansion weight usable: negative and NaN weights become
+/// zero, and infinite weights the largest finite weight.
+fn clamp_weight(weight: f32) -> f32 {
+    if weight.is_nan() { 0.0 } else { weight.clamp(0.0, f32::MAX) }
+}
+
+/// Finds where a box of `size` begins within `space` along one axis.
+/// Centering takes precedence over the edges, and anchoring to both
+/// the `start` and `end` edge at once also centers. Otherwise the box
+/// sticks to the `end` edge if asked to, or the `start` edge by default.
+fn anchor<T: Scalar>(space: T, size: T, flags: CellFlags, start: CellFlags, end: CellFlags, center: CellFlags) -> T {
+    if flags.contains(center) || flags.contains(start | end) {
+        // halve the leftover space in one go, so integers round only once
+        (space - size) / T::from_usize(2)
+    } else if flags.contains(end) {
+        // take the space and remove the size, will anchor us to the far side
+        space - size
+    } else {
+        // Anchoring to the start is the same as doing nothing, so we just put this there.
+        T::zero()
+    }
+}
+
 bitflags! {
     pub struct CellFlags: u16 {
         const None                   = 0b0000_0000_0000_0000;
@@ -162,105 +361,550 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// Records which cell properties have been set explicitly, rather
+    /// than being left for row, column or table defaults to decide.
+    /// Widths and heights are recorded separately.
+    pub struct Specified: u8 {
+        const None            = 0b0000_0000;
+        const MinimumWidth    = 0b0000_0001;
+        const MinimumHeight   = 0b0000_0010;
+        const MaximumWidth    = 0b0000_0100;
+        const MaximumHeight   = 0b0000_1000;
+        const PreferredWidth  = 0b0001_0000;
+        const PreferredHeight = 0b0010_0000;
+        const Padding         = 0b0100_0000;
+        const ExpandWeight    = 0b1000_0000;
+        const MinimumSize     = Self::MinimumWidth.bits | Self::MinimumHeight.bits;
+        const MaximumSize     = Self::MaximumWidth.bits | Self::MaximumHeight.bits;
+        const PreferredSize   = Self::PreferredWidth.bits | Self::PreferredHeight.bits;
+    }
+}
+
+/// Flags are stored as a list of their names, so that layouts written
+/// by hand stay readable.
+#[cfg(feature = "serde")]
+macro_rules! serde_flags {
+    ($flags:ident, $what:expr, [$($name:ident),*]) => {
+        impl Serialize for $flags {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                let mut names: Vec<&str> = Vec::new();
+                $(
+                    if self.contains($flags::$name) {
+                        names.push(stringify!($name));
+                    }
+                )*
+                names.serialize(serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $flags {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let mut flags = $flags::None;
+                for name in Vec::<String>::deserialize(deserializer)? {
+                    flags |= match name.as_str() {
+                        $( stringify!($name) => $flags::$name, )*
+                        _ => return Err(de::Error::custom(format!("unknown {} `{}`", $what, name))),
+                    };
+                }
+                Ok(flags)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+serde_flags!(CellFlags, "cell flag", [
+    ExpandHorizontal, ExpandVertical, FillHorizontal, FillVertical,
+    AnchorTop, AnchorBottom, AnchorLeft, AnchorRight,
+    AnchorHorizontalCenter, AnchorVerticalCenter, Uniform
+]);
+
 /// Allows a closure to ensure a layout item has been placed where the
 /// layout engine decided it should go. The parameters are the `x`,
 /// `y` coordinates, and the `width`/`height` respectively.
-pub type PositioningFn = FnMut(f32, f32, f32, f32);
+pub type PositioningFn<T = f32> = dyn FnMut(T, T, T, T);
+
+/// Lets a cell work out how tall it is once it knows how wide it will
+/// be, as for text which wraps. Receives the width left inside the
+/// cell's padding, and returns the preferred height of its contents.
+pub type HeightForWidthFn<T = f32> = dyn Fn(T) -> T;
+
+/// Something which can be laid out by a table, such as a widget. Items
+/// measure themselves, so their sizes need not be copied into the cell,
+/// and are told where they were placed instead of using a callback.
+pub trait LayoutItem<T = f32> {
+    /// Returns the minimum, preferred and maximum size of the item.
+    fn measure(&self) -> SizeGrouping<T>;
+
+    /// Returns the item's preferred height at the given width, for items
+    /// such as wrapping text whose height depends on their width. Items
+    /// which do not care return `None`, as they do by default.
+    fn height_for_width(&self, _width: T) -> Option<T> {
+        None
+    }
+
+    /// Moves the item to the `x`, `y` coordinates and resizes it to the
+    /// `width` and `height` the table decided on.
+    fn place(&mut self, x: T, y: T, width: T, height: T);
+}
 
 /// Encapsulates all properties for a cell; contributes to eventual layout decisions.
-pub struct CellProperties {
+pub struct CellProperties<T = f32> {
     /// Controls the desired sizes for this cell.
-    pub size: SizeGrouping,
+    pub size: SizeGrouping<T>,
     /// Controls various binary flags for the cell.
     pub flags: CellFlags,
     /// Controls how many columns this cell will occupy.
-    pub colspan: u8,
+    pub colspan: usize,
+    /// Controls how many rows this cell will occupy.
+    pub rowspan: usize,
+    /// Space reserved around the cell's contents, inside the cell.
+    pub padding: Rectangle<T>,
+    /// Share of extra space this cell's columns and rows receive when
+    /// expanding, relative to other expanding columns and rows.
+    pub expand_weight: f32,
+    /// Flags turned off for this cell, even if row, column or table
+    /// defaults turn them on.
+    pub unset: CellFlags,
+    /// Records which properties were set through the builder methods, so
+    /// they override row, column and table defaults even when set to
+    /// their default values.
+    pub specified: Specified,
+    /// Names the cell, so callbacks can be bound to it later on with
+    /// `TableLayout::bind`; as when a layout has been deserialized.
+    pub id: Option<String>,
     /// Applies positioning updates for this cell. Note that this
     /// value always becomes `None` when cloned, so you cannot set
     /// default callbacks for cell policies.
-    pub callback: Option<Box<PositioningFn>>
+    pub callback: Option<Box<PositioningFn<T>>>,
+    /// Table laid out within this cell's fitted box. Like `callback`,
+    /// this value always becomes `None` when cloned.
+    pub table: Option<Box<TableLayout<T>>>,
+    /// Measures the cell's height once its width is known. Like
+    /// `callback`, this value always becomes `None` when cloned.
+    pub height_for_width: Option<Box<HeightForWidthFn<T>>>,
+    /// Item measured along with this cell and told where it was placed.
+    /// Like `callback`, this value always becomes `None` when cloned.
+    pub item: Option<Box<dyn LayoutItem<T>>>,
 }
 
-impl Default for CellProperties {
+impl<T: Scalar> Default for CellProperties<T> {
     fn default() -> Self {
         CellProperties{
             size: Default::default(),
             flags: CellFlags::None,
             colspan: 1,
+            rowspan: 1,
+            padding: Default::default(),
+            expand_weight: 1.0,
+            unset: CellFlags::None,
+            specified: Specified::None,
+            id: None,
             callback: None,
+            table: None,
+            height_for_width: None,
+            item: None,
         }
     }
 }
 
-impl Clone for CellProperties {
+impl<T: Scalar> Clone for CellProperties<T> {
     fn clone(&self) -> Self {
         CellProperties{
             size: self.size.clone(),
             flags: self.flags,
             colspan: self.colspan,
+            rowspan: self.rowspan,
+            padding: self.padding.clone(),
+            expand_weight: self.expand_weight,
+            unset: self.unset,
+            specified: self.specified,
+            id: self.id.clone(),
             callback: None,
+            table: None,
+            height_for_width: None,
+            item: None,
+        }
+    }
+}
+
+/// Width and height of one of a cell's sizes, as stored by serde. Either
+/// may be left out, to be decided by row, column and table defaults.
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SizeRepr<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width:  Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<T>,
+}
+
+#[cfg(feature = "serde")]
+impl<T: Scalar> SizeRepr<T> {
+    /// Keeps whichever axes of `size` were set explicitly, or differ
+    /// from the `default` size; `None` if neither was.
+    fn written(size: &Size<T>, default: &Size<T>, width: bool, height: bool) -> Option<Self> {
+        let repr = SizeRepr{
+            width: if width || size.width != default.width { Some(size.width) } else { None },
+            height: if height || size.height != default.height { Some(size.height) } else { None },
+        };
+        if repr.width.is_none() && repr.height.is_none() { None } else { Some(repr) }
+    }
+
+    /// Copies whichever axes are present into `size`, marking them with
+    /// the `width` and `height` bits of `specified`.
+    fn read(self, size: &mut Size<T>, specified: &mut Specified, width: Specified, height: Specified) {
+        if let Some(w) = self.width {
+            size.width = w;
+            *specified |= width;
+        }
+        if let Some(h) = self.height {
+            size.height = h;
+            *specified |= height;
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize, Deserialize)]
+struct SizeGroupingRepr<T> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    minimum:   Option<SizeRepr<T>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    preferred: Option<SizeRepr<T>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maximum:   Option<SizeRepr<T>>,
+}
+
+/// Cell properties as read by serde. Whatever is present counts as set
+/// explicitly, and whatever is missing is left to the defaults.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(bound(deserialize = "T: Scalar + Deserialize<'de>"))]
+struct CellRepr<T> {
+    size:          Option<SizeGroupingRepr<T>>,
+    flags:         Option<CellFlags>,
+    unset:         Option<CellFlags>,
+    colspan:       Option<usize>,
+    rowspan:       Option<usize>,
+    padding:       Option<Rectangle<T>>,
+    expand_weight: Option<f32>,
+    id:            Option<String>,
+    table:         Option<Box<TableLayout<T>>>,
+}
+
+/// Cells are written with only the properties which were set explicitly
+/// or differ from their defaults, so `specified` is never stored; it is
+/// worked out again from whichever properties are present. Callbacks,
+/// height for width closures and layout items are left out.
+#[cfg(feature = "serde")]
+impl<T: Scalar + Serialize> Serialize for CellProperties<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let defaults = CellProperties::<T>::default();
+        let specified = self.specified;
+        let size = SizeGroupingRepr{
+            minimum: SizeRepr::written(&self.size.minimum, &defaults.size.minimum,
+                                       specified.contains(Specified::MinimumWidth), specified.contains(Specified::MinimumHeight)),
+            preferred: SizeRepr::written(&self.size.preferred, &defaults.size.preferred,
+                                         specified.contains(Specified::PreferredWidth), specified.contains(Specified::PreferredHeight)),
+            maximum: SizeRepr::written(&self.size.maximum, &defaults.size.maximum,
+                                       specified.contains(Specified::MaximumWidth), specified.contains(Specified::MaximumHeight)),
+        };
+
+        let mut state = serializer.serialize_struct("CellProperties", 9)?;
+        if size.minimum.is_some() || size.preferred.is_some() || size.maximum.is_some() {
+            state.serialize_field("size", &size)?;
+        }
+        if !self.flags.is_empty() {
+            state.serialize_field("flags", &self.flags)?;
+        }
+        if !self.unset.is_empty() {
+            state.serialize_field("unset", &self.unset)?;
+        }
+        if self.colspan != defaults.colspan {
+            state.serialize_field("colspan", &self.colspan)?;
+        }
+        if self.rowspan != defaults.rowspan {
+            state.serialize_field("rowspan", &self.rowspan)?;
+        }
+        if specified.contains(Specified::Padding) || self.padding != defaults.padding {
+            state.serialize_field("padding", &self.padding)?;
+        }
+        if specified.contains(Specified::ExpandWeight) || self.expand_weight != defaults.expand_weight {
+            state.serialize_field("expand_weight", &self.expand_weight)?;
+        }
+        if let Some(id) = &self.id {
+            state.serialize_field("id", id)?;
+        }
+        if let Some(table) = &self.table {
+            state.serialize_field("table", table)?;
+        }
+        state.end()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, T: Scalar + Deserialize<'de>> Deserialize<'de> for CellProperties<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let repr = CellRepr::<T>::deserialize(deserializer)?;
+        let mut cell = CellProperties::default();
+        if let Some(size) = repr.size {
+            if let Some(s) = size.minimum {
+                s.read(&mut cell.size.minimum, &mut cell.specified, Specified::MinimumWidth, Specified::MinimumHeight);
+            }
+            if let Some(s) = size.preferred {
+                s.read(&mut cell.size.preferred, &mut cell.specified, Specified::PreferredWidth, Specified::PreferredHeight);
+            }
+            if let Some(s) = size.maximum {
+                s.read(&mut cell.size.maximum, &mut cell.specified, Specified::MaximumWidth, Specified::MaximumHeight);
+            }
+        }
+        if let Some(flags) = repr.flags {
+            cell.flags = flags;
+        }
+        if let Some(unset) = repr.unset {
+            cell.unset = unset;
+        }
+        if let Some(span) = repr.colspan {
+            cell.colspan = span;
+        }
+        if let Some(span) = repr.rowspan {
+            cell.rowspan = span;
+        }
+        if let Some(padding) = repr.padding {
+            cell = cell.padding(padding);
         }
+        if let Some(weight) = repr.expand_weight {
+            cell = cell.expand_weight(weight);
+        }
+        cell.id = repr.id;
+        cell.table = repr.table;
+        Ok(cell)
     }
 }
 
-pub enum LayoutOp {
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(bound(serialize = "T: Scalar + Serialize", deserialize = "T: Scalar + Deserialize<'de>")))]
+pub enum LayoutOp<T = f32> {
     /// Inserts a cell in the resulting layout.
-    Cell(CellProperties),
+    Cell(CellProperties<T>),
     /// Inserts a row break in the resulting layout.
     Row,
 }
 
-pub struct TableLayout {
-    pub cell_defaults:   CellProperties,
-    pub row_defaults:    BTreeMap<u8, CellProperties>,
-    pub column_defaults: BTreeMap<u8, CellProperties>,
-    pub opcodes:         Vec<LayoutOp>,
+/// Position and size of a box within the table.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Bounds<T = f32> {
+    pub x:      T,
+    pub y:      T,
+    pub width:  T,
+    pub height: T,
+}
+
+/// Where the layout engine decided a cell should go.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Placement<T = f32> {
+    /// Counts which cell this is, in the order cells were added to the table.
+    pub index:   usize,
+    /// Row the cell begins at.
+    pub row:     usize,
+    /// Column the cell begins at.
+    pub column:  usize,
+    /// Area given to the cell, including its padding.
+    pub cell:    Bounds<T>,
+    /// Area given to the cell's contents; this is what callbacks receive.
+    pub content: Bounds<T>,
+}
+
+/// Decides what happens when the cells of a table cannot shrink enough
+/// to fit the space they are imposed on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Degradation {
+    /// Columns or rows keep their minimum sizes, and the layout runs past
+    /// the right or bottom edge of the table.
+    Overflow,
+    /// Columns or rows are shrunk below their minimum sizes, in proportion
+    /// to those minimums, so the layout fits exactly.
+    Squash,
+}
+
+/// Describes an axis which did not fit in the space it was given.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Overflow<T = f32> {
+    /// Which axis ran out of space.
+    pub axis:        Axis,
+    /// How much space was missing after every column or row had been
+    /// shrunk to its minimum size.
+    pub amount:      T,
+    /// What the engine did to cope.
+    pub degradation: Degradation,
+}
+
+/// Returned when a layout could not be solved within the space it was
+/// imposed on. The layout is still performed, in a degraded form.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Overconstrained<T = f32> {
+    /// Every axis which did not fit, including those of nested tables.
+    pub overflows:  Vec<Overflow<T>>,
+    /// Where cells were placed once the layout had been degraded.
+    pub placements: Vec<Placement<T>>,
+}
+
+impl<T: Scalar> fmt::Display for Overconstrained<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "layout is over-constrained")?;
+        for (i, o) in self.overflows.iter().enumerate() {
+            let how = match o.degradation {
+                Degradation::Overflow => "overflowed",
+                Degradation::Squash => "squashed",
+            };
+            write!(f, "{} {:?} axis short by {} ({})",
+                   if i == 0 { ":" } else { ";" }, o.axis, o.amount, how)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Scalar> Error for Overconstrained<T> {}
+
+/// A single step taken by the layout solver, as reported to the tracer
+/// given to `TableLayout::solve_traced`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TraceEvent<T = f32> {
+    /// Solving has begun for a table of this many rows and columns.
+    Imposing{rows: usize, columns: usize},
+    /// A cell has been measured; `index` counts cells in the order they
+    /// were added to the table.
+    Measured{index: usize, flags: CellFlags},
+    /// A column or row will receive extra space, because one of its
+    /// cells expands with the given weight.
+    Flagged{axis: Axis, track: usize, weight: f32},
+    /// A column or row was given `amount` of extra space.
+    Expanded{axis: Axis, track: usize, amount: T},
+    /// A column or row reached its maximum size while expanding; extra
+    /// space it could not take is handed to the others.
+    Clamped{axis: Axis, track: usize},
+    /// Columns or rows must shrink by `error` in total, taken from
+    /// `total_slack` worth of room between preferred and minimum sizes.
+    Shrinking{axis: Axis, error: T, total_slack: T},
+    /// A column or row gave up `amount`, being its `share` of the slack.
+    Shrunk{axis: Axis, track: usize, share: f32, amount: T},
+    /// Columns or rows could not shrink enough, so the layout was degraded.
+    Degraded(Overflow<T>),
+    /// A cell measured its height at `width`, once its columns were sized.
+    HeightForWidth{index: usize, width: T, height: T},
+}
+
+/// Hands a solver step to the tracer, and to the `log` facade if enabled.
+fn trace<T: Scalar>(tracer: &mut dyn FnMut(&TraceEvent<T>), event: TraceEvent<T>) {
+    #[cfg(feature = "log")]
+    trace!("{:?}", event);
+    tracer(&event);
+}
+
+/// Grid slot a cell was assigned to, after accounting for cells which
+/// span down from earlier rows.
+struct Slot {
+    /// Index of the cell within the layout's opcodes.
+    op:      usize,
+    /// Counts which cell this is, ignoring row breaks.
+    cell:    usize,
+    row:     usize,
+    col:     usize,
+    rowspan: usize,
+    colspan: usize,
+}
+
+/// Size preferences gathered from every cell in a table, before any
+/// space has been handed out.
+struct Measurement<T> {
+    slots:       Vec<Slot>,
+    /// Properties of each slotted cell, with defaults layered underneath.
+    styles:      Vec<CellProperties<T>>,
+    /// Sizes each slotted cell's contents are laid out with.
+    sizes:       Vec<SizeGrouping<T>>,
+    col_sizes:   Vec<SizeGrouping<T>>,
+    row_sizes:   Vec<SizeGrouping<T>>,
+    /// Expansion weight of each column; zero if it does not expand.
+    x_weights:   Vec<f32>,
+    /// Expansion weight of each row; zero if it does not expand.
+    y_weights:   Vec<f32>,
+}
+
+/// Everything decided while solving a table, as drawn by `svg::render`.
+struct Arrangement<T> {
+    /// Where each column begins, and how wide it is.
+    columns:    Vec<(T, T)>,
+    /// Where each row begins, and how tall it is.
+    rows:       Vec<(T, T)>,
+    /// Properties of each placed cell, with defaults layered underneath.
+    styles:     Vec<CellProperties<T>>,
+    placements: Vec<Placement<T>>,
+    overflows:  Vec<Overflow<T>>,
+}
 
-    pub row: u8,
-    pub column: u8,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default, bound(serialize = "T: Scalar + Serialize", deserialize = "T: Scalar + Deserialize<'de>")))]
+pub struct TableLayout<T = f32> {
+    pub cell_defaults:   CellProperties<T>,
+    pub row_defaults:    BTreeMap<usize, CellProperties<T>>,
+    pub column_defaults: BTreeMap<usize, CellProperties<T>>,
+    pub opcodes:         Vec<LayoutOp<T>>,
+
+    /// Space reserved between the table's edges and its cells.
+    pub padding:            Rectangle<T>,
+    /// Space left between adjacent columns.
+    pub horizontal_spacing: T,
+    /// Space left between adjacent rows.
+    pub vertical_spacing:   T,
+    /// What to do when cells cannot shrink enough to fit.
+    pub degradation:        Degradation,
+    /// Whether columns, rows and cell contents are kept to whole units.
+    pub integer_grid:       bool,
+    /// Device pixels per unit, if edges are snapped to device pixels.
+    pub pixel_scale:        Option<f32>,
+
+    pub row: usize,
+    pub column: usize,
 }
 
 impl CellProperties {
     pub fn new() -> Self {
         Default::default()
     }
+}
 
-    /// Inherits the default settings as determined by a
-    /// `TableLayout`. Will first try to match the defaults for the
-    /// column this would be added to, then the row, then the fallback
-    /// defaults. Note that these defaults apply only if the cell
-    /// was added next and if the defaults have not been changed
-    /// since. The correct use of `with_defaults` is to initialize
-    /// `CellProperties` for immediate insertion to a layout.
-    pub fn with_defaults(layout: &TableLayout) -> Self {
-        // try to get the column default
-        let column_value = layout.column_defaults.get(&layout.column);
-        if column_value.is_some() {
-            return (*column_value.unwrap()).clone();
-        }
-
-        // try to get the row default
-        let row_value = layout.row_defaults.get(&layout.row);
-        if row_value.is_some() {
-            return (*row_value.unwrap()).clone();
-        }
-
-        // just get the default i guess
-        CellProperties{..layout.cell_defaults.clone()}
+impl<T: Scalar> CellProperties<T> {
+    /// Creates properties which inherit the default settings of a
+    /// `TableLayout`. Table, row and column defaults are layered
+    /// underneath every cell when the layout is imposed, so this is
+    /// the same as `new`; it remains for code written before that.
+    #[deprecated(note = "defaults are layered at impose time; use CellProperties::new")]
+    pub fn with_defaults(_layout: &TableLayout<T>) -> Self {
+        Default::default()
     }
 
-    pub fn minimum_size(mut self, minimum: Size) -> Self {
+    pub fn minimum_size(mut self, minimum: Size<T>) -> Self {
         self.size.minimum = minimum;
+        self.specified |= Specified::MinimumSize;
         self
     }
 
-    pub fn maximum_size(mut self, maximum: Size) -> Self {
+    pub fn maximum_size(mut self, maximum: Size<T>) -> Self {
         self.size.maximum = maximum;
+        self.specified |= Specified::MaximumSize;
         self
     }
 
-    pub fn preferred_size(mut self, preferred: Size) -> Self {
+    pub fn preferred_size(mut self, preferred: Size<T>) -> Self {
         self.size.preferred = preferred;
+        self.specified |= Specified::PreferredSize;
         self
     }
 
@@ -329,55 +973,239 @@ impl CellProperties {
         self
     }
 
+    /// Sets how large a share of extra space this cell's columns and
+    /// rows receive when expanding. Defaults to `1.0`; a column with a
+    /// weight of `3.0` grows three times as much as one with `1.0`.
+    /// Negative and NaN weights count as zero, and an infinite weight as
+    /// the largest finite one.
+    pub fn expand_weight(mut self, weight: f32) -> Self {
+        self.expand_weight = clamp_weight(weight);
+        self.specified |= Specified::ExpandWeight;
+        self
+    }
+
     pub fn uniform(mut self) -> Self {
         self.flags |= CellFlags::Uniform;
         self
     }
 
-    pub fn colspan(mut self, span: u8) -> Self {
+    /// Turns `flags` off for this cell, even if row, column or table
+    /// defaults turn them on; as with `.unset(CellFlags::ExpandHorizontal)`
+    /// for a cell which should not expand in a row of cells which do.
+    pub fn unset(mut self, flags: CellFlags) -> Self {
+        self.flags.remove(flags);
+        self.unset |= flags;
+        self
+    }
+
+    pub fn colspan(mut self, span: usize) -> Self {
         self.colspan = span;
         self
     }
 
-    pub fn callback(mut self, fun: Box<PositioningFn>) -> Self {
+    pub fn rowspan(mut self, span: usize) -> Self {
+        self.rowspan = span;
+        self
+    }
+
+    pub fn padding(mut self, padding: Rectangle<T>) -> Self {
+        self.padding = padding;
+        self.specified |= Specified::Padding;
+        self
+    }
+
+    /// Names the cell, so a callback can be bound to it later on with
+    /// `TableLayout::bind`.
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Option::Some(id.to_string());
+        self
+    }
+
+    pub fn callback(mut self, fun: Box<PositioningFn<T>>) -> Self {
         self.callback = Option::Some(fun);
         self
     }
+
+    /// Places a table inside of this cell. The table's own sizes are
+    /// joined with the cell's, and it is imposed within the cell's
+    /// fitted box whenever the outer table is imposed.
+    pub fn table(mut self, table: TableLayout<T>) -> Self {
+        self.table = Option::Some(Box::new(table));
+        self
+    }
+
+    /// Has the cell's preferred height measured by `fun` once its columns
+    /// have been sized, instead of being fixed up front. The width given
+    /// to `fun` is whatever the cell's columns leave inside its padding.
+    pub fn height_for_width(mut self, fun: Box<HeightForWidthFn<T>>) -> Self {
+        self.height_for_width = Option::Some(fun);
+        self
+    }
+
+    /// Lays out an item in this cell. The item's sizes are joined with
+    /// the cell's when measuring, and the item is told where its fitted
+    /// box ended up whenever the table is imposed.
+    pub fn item(mut self, item: Box<dyn LayoutItem<T>>) -> Self {
+        self.item = Option::Some(item);
+        self
+    }
+
+    /// Layers whatever `other` specifies on top of these properties.
+    /// Sizes, padding and expansion weight replace ours when they were
+    /// set explicitly, or differ from their defaults. Anchors replace
+    /// ours when `other` anchors along the same axis, flags `other` has
+    /// unset are removed, and all other flags are added together.
+    fn overlay(&mut self, other: &CellProperties<T>) {
+        let defaults = CellProperties::<T>::default();
+        let specified = other.specified;
+        overlay_size(&mut self.size.minimum, &other.size.minimum, &defaults.size.minimum,
+                     specified.contains(Specified::MinimumWidth), specified.contains(Specified::MinimumHeight));
+        overlay_size(&mut self.size.maximum, &other.size.maximum, &defaults.size.maximum,
+                     specified.contains(Specified::MaximumWidth), specified.contains(Specified::MaximumHeight));
+        overlay_size(&mut self.size.preferred, &other.size.preferred, &defaults.size.preferred,
+                     specified.contains(Specified::PreferredWidth), specified.contains(Specified::PreferredHeight));
+        if other.specified.contains(Specified::Padding) || other.padding != defaults.padding {
+            self.padding = other.padding.clone();
+        }
+        if other.specified.contains(Specified::ExpandWeight) || other.expand_weight != defaults.expand_weight {
+            self.expand_weight = other.expand_weight;
+        }
+        self.specified |= other.specified;
+
+        let horizontal = CellFlags::AnchorLeft | CellFlags::AnchorRight | CellFlags::AnchorHorizontalCenter;
+        let vertical = CellFlags::AnchorTop | CellFlags::AnchorBottom | CellFlags::AnchorVerticalCenter;
+        for anchors in &[horizontal, vertical] {
+            if other.flags.intersects(*anchors) {
+                self.flags.remove(*anchors);
+            }
+        }
+        self.flags.remove(other.unset);
+        self.flags |= other.flags;
+    }
 }
 
-impl TableLayout {
-    pub fn new() -> TableLayout {
+impl<T: Scalar> Default for TableLayout<T> {
+    fn default() -> Self {
         TableLayout {
             cell_defaults:   Default::default(),
             row_defaults:    BTreeMap::new(),
             column_defaults: BTreeMap::new(),
             opcodes:         Vec::new(),
+            padding:            Default::default(),
+            horizontal_spacing: T::zero(),
+            vertical_spacing:   T::zero(),
+            degradation:        Degradation::Overflow,
+            integer_grid:       false,
+            pixel_scale:        None,
             row: 0,
             column: 0,
         }
     }
+}
+
+impl TableLayout {
+    pub fn new() -> TableLayout {
+        Default::default()
+    }
+}
 
+impl<T: Scalar> TableLayout<T> {
     /// Calculates the number of rows and columns which exist in this table layout.
-    pub fn get_rows_cols(&self) -> (u8, u8) {
-        let mut cols   = 0;
-        let mut colcur = 0;
-        let mut rows   = 0;
+    pub fn get_rows_cols(&self) -> (usize, usize) {
+        let mut cols = 0;
+        let mut rows = 0;
 
         for op in &self.opcodes {
-            match op {
-                LayoutOp::Cell(cp) => { colcur += cp.colspan },
-                LayoutOp::Row => { cols = max(cols, colcur); colcur = 0; rows += 1 },
+            if let LayoutOp::Row = op {
+                rows += 1;
             }
         }
 
-        if colcur > 0 {
-            cols = max(cols, colcur);
-            rows += 1;
+        // Cells may hang off the final row break, or span further down than
+        // the last row which was explicitly declared.
+        for slot in self.get_slots() {
+            rows = max(rows, slot.row + slot.rowspan);
+            cols = max(cols, slot.col + slot.colspan);
         }
 
         (rows, cols)
     }
 
+    /// Assigns each cell to the grid slot it begins at. Slots which are
+    /// still occupied by a cell spanning down from a previous row are
+    /// skipped over, so the cell lands in the next free column.
+    fn get_slots(&self) -> Vec<Slot> {
+        let mut slots: Vec<Slot> = Vec::with_capacity(self.opcodes.len());
+        // Row each column stays reserved until, by cells spanning down.
+        let mut reserved: Vec<usize> = Vec::new();
+        let mut row: usize = 0;
+        let mut col: usize = 0;
+        let mut cell: usize = 0;
+
+        for (i, op) in self.opcodes.iter().enumerate() {
+            match op {
+                LayoutOp::Cell(cp) => {
+                    cell += 1;
+
+                    // If a cell has a span of zero, that is kind of stupid and it basically doesn't exist.
+                    if cp.colspan == 0 || cp.rowspan == 0 {
+                        continue
+                    }
+
+                    // Every column the cell spans must be free, not just the first.
+                    while (col..col+cp.colspan).any(|c| reserved.get(c).is_some_and(|until| *until > row)) {
+                        col += 1;
+                    }
+
+                    // Reserve slots in the rows below this one; the current
+                    // row is covered by advancing the column cursor.
+                    if cp.rowspan > 1 {
+                        if reserved.len() < col + cp.colspan {
+                            reserved.resize(col + cp.colspan, 0);
+                        }
+                        for until in &mut reserved[col..col+cp.colspan] {
+                            *until = max(*until, row + cp.rowspan);
+                        }
+                    }
+
+                    slots.push(Slot{
+                        op: i,
+                        cell: cell - 1,
+                        row,
+                        col,
+                        rowspan: cp.rowspan,
+                        colspan: cp.colspan,
+                    });
+                    col += cp.colspan;
+                }
+                // flop to a new row
+                LayoutOp::Row => {
+                    row += 1;
+                    col = 0;
+                }
+            }
+        }
+
+        slots
+    }
+
+    /// Merges the table defaults, row defaults, column defaults and the
+    /// cell's own properties, in that order, field by field. Spans,
+    /// callbacks and nested tables are never inherited.
+    fn resolve(&self, cp: &CellProperties<T>, row: usize, col: usize) -> CellProperties<T> {
+        let mut style = self.cell_defaults.clone();
+        if let Some(defaults) = self.row_defaults.get(&row) {
+            style.overlay(defaults);
+        }
+        if let Some(defaults) = self.column_defaults.get(&col) {
+            style.overlay(defaults);
+        }
+        style.overlay(cp);
+        style.colspan = cp.colspan;
+        style.rowspan = cp.rowspan;
+        style
+    }
+
     /// Removes all layout declarations from the table. Does not remove row or column defaults.
     pub fn clear(&mut self) {
         self.row = 0;
@@ -390,238 +1218,667 @@ impl TableLayout {
         self.clear();
         self.row_defaults.clear();
         self.column_defaults.clear();
-        self.cell_defaults = Default::default()
+        self.cell_defaults = Default::default();
+        self.padding = Default::default();
+        self.horizontal_spacing = T::zero();
+        self.vertical_spacing = T::zero();
+        self.degradation = Degradation::Overflow;
+        self.integer_grid = false;
+        self.pixel_scale = None
     }
 
-    /// Adds a new row to the layout.
-    pub fn with_row(&mut self) -> &mut Self {
-        self.opcodes.push(LayoutOp::Row);
-        self.row += 1;
-        self.column = 0;
+    /// Sets the properties every cell in the table inherits.
+    pub fn with_cell_defaults(&mut self, properties: CellProperties<T>) -> &mut Self {
+        self.cell_defaults = properties;
         self
     }
 
-    /// Hands the cell off to the layout.
-    pub fn with_cell(&mut self, properties: CellProperties) -> &mut Self {
-        self.column += properties.colspan;
-        self.opcodes.push(LayoutOp::Cell(properties));
+    /// Sets the properties every cell starting in the given row inherits.
+    /// These take precedence over the table's cell defaults.
+    pub fn with_row_defaults(&mut self, row: usize, properties: CellProperties<T>) -> &mut Self {
+        self.row_defaults.insert(row, properties);
         self
     }
 
-    pub fn impose(&mut self, width: f32, height: f32) {
-        let mut row: u8 = 0;
-        let mut col: u8 = 0;
+    /// Sets the properties every cell starting in the given column inherits.
+    /// These take precedence over row defaults and the table's cell defaults.
+    pub fn with_column_defaults(&mut self, column: usize, properties: CellProperties<T>) -> &mut Self {
+        self.column_defaults.insert(column, properties);
+        self
+    }
 
-        let (total_rows, total_cols) = self.get_rows_cols();
-        if total_cols == 0 {return} // short-circuiting opportunity
-        eprintln!("Imposing matrix: {}x{}", total_rows, total_cols);
+    /// Sets the space reserved between the table's edges and its cells.
+    pub fn with_padding(&mut self, padding: Rectangle<T>) -> &mut Self {
+        self.padding = padding;
+        self
+    }
 
-        let mut col_sizes: Vec<SizeGrouping> = Vec::with_capacity(total_cols as usize);
-        // XXX resize_with is unstable, but would do what we want just fine
-        for _i in 0..total_cols {
-            col_sizes.push(Default::default());
-        }
+    /// Sets the space left between adjacent columns and rows respectively.
+    pub fn with_spacing(&mut self, horizontal: T, vertical: T) -> &mut Self {
+        self.horizontal_spacing = horizontal;
+        self.vertical_spacing = vertical;
+        self
+    }
 
-        // XXX resize_with is unstable, but would do what we want just fine
-        let mut row_sizes: Vec<SizeGrouping> = Vec::with_capacity(total_cols as usize);
-        for _i in 0..total_rows {
-            row_sizes.push(Default::default());
-        }
+    /// Sets what happens when cells cannot shrink enough to fit.
+    pub fn with_degradation(&mut self, degradation: Degradation) -> &mut Self {
+        self.degradation = degradation;
+        self
+    }
 
-        let mut has_xexpand: Vec<bool> = Vec::with_capacity(total_cols as usize);
-        for _i in 0..total_cols {
-            has_xexpand.push(false);
-        }
+    /// Keeps every column, row and cell content to whole units, as for
+    /// terminals where each position is a character cell. Space that
+    /// would be split into fractions is rounded down, and the units left
+    /// over go to the columns or rows which lost the most to rounding,
+    /// so they still add up to the space available.
+    pub fn with_integer_grid(&mut self, integer_grid: bool) -> &mut Self {
+        self.integer_grid = integer_grid;
+        self
+    }
 
-        let mut has_yexpand: Vec<bool> = Vec::with_capacity(total_rows as usize);
-        for _i in 0..total_rows {
-            has_yexpand.push(false);
-        }
+    /// Rounds the edges of every cell and its contents to the nearest
+    /// device pixel, `scale` being how many device pixels make up one
+    /// unit (ex. `2.0` on a high DPI display). Sizes are taken from the
+    /// rounded edges, so adjacent cells share exact boundaries and the
+    /// table keeps its total size. `None` turns snapping off.
+    pub fn with_pixel_snapping(&mut self, scale: Option<f32>) -> &mut Self {
+        self.pixel_scale = scale;
+        self
+    }
 
-        // We determine size preferences for each column in the layout.
-        for op in &self.opcodes {
-            match op {
-                LayoutOp::Cell(cp) => {
-                    match cp.colspan {
-                        // If a cell has a span of zero, that is kind of stupid and it basically doesn't exist.
-                        0 => {},
-                        _ => {
-                            let midget = cp.size.spread(f32::from(cp.colspan));
-                            eprintln!("{:#?}", cp.flags);
-                            row_sizes[row as usize] =
-                                SizeGrouping::join(&row_sizes[row as usize], &cp.size);
-                            if cp.flags.contains(CellFlags::ExpandVertical) {
-                                eprintln!("flagging row {} for x-expansion", row);
-                                has_yexpand[row as usize] = true
-                            }
-                            for _i in 0..cp.colspan {
-                                if cp.flags.contains(CellFlags::ExpandHorizontal) {
-                                    eprintln!("flagging col {} for x-expansion", col);
-                                    has_xexpand[col as usize] = true
-                                }
-                                col_sizes[col as usize] = SizeGrouping::join(&col_sizes[col as usize], &midget);
-                                col += 1;
-                            }
-                        }
-                    }
+    /// Finds the first cell named `id`, looking inside nested tables as
+    /// well. Useful for attaching whatever could not be stored along
+    /// with a layout, such as layout items.
+    pub fn find_cell(&mut self, id: &str) -> Option<&mut CellProperties<T>> {
+        for op in &mut self.opcodes {
+            if let LayoutOp::Cell(cp) = op {
+                if cp.id.as_deref() == Some(id) {
+                    return Some(cp)
                 }
-                // flop to a new row
-                LayoutOp::Row => {
-                    row += 1;
-                    col = 0;
+                if let Some(table) = &mut cp.table {
+                    if let Some(found) = table.find_cell(id) {
+                        return Some(found)
+                    }
                 }
             }
         }
+        None
+    }
+
+    /// Sets the callback of the first cell named `id`, looking inside
+    /// nested tables as well. Returns whether such a cell was found.
+    pub fn bind(&mut self, id: &str, fun: Box<PositioningFn<T>>) -> bool {
+        match self.find_cell(id) {
+            Some(cp) => {
+                cp.callback = Option::Some(fun);
+                true
+            }
+            None => false,
+        }
+    }
 
-        let mut slack: Vec<f32> = Vec::new();
+    /// Adds a new row to the layout.
+    pub fn with_row(&mut self) -> &mut Self {
+        self.opcodes.push(LayoutOp::Row);
+        self.row += 1;
+        self.column = 0;
+        self
+    }
 
-        // Calculate error along width distribution
-        let mut error = width;
-        for c in &col_sizes {
-            // Error is what remains once we have given each column its preferred size.
-            error -= c.preferred.width;
-        }
-
-        if error > 0.0 { // Extra space; relax the layout if we need to
-            // Figure out how many columns are expanding horizontally.
-            let expansions = has_xexpand.iter().filter(|x| **x).count();
-            if expansions > 0 {
-                let amount = error / expansions as f32;
-                for (i, e) in has_xexpand.iter().enumerate() {
-                    eprintln!("Expanding column {} = {}", i, e);
-                    if *e {
-                        col_sizes[i].preferred.width += amount;
-                    }
+    /// Hands the cell off to the layout.
+    pub fn with_cell(&mut self, properties: CellProperties<T>) -> &mut Self {
+        self.column += properties.colspan;
+        self.opcodes.push(LayoutOp::Cell(properties));
+        self
+    }
+
+    /// Lays the table out within the given width and height, then hands
+    /// each cell's fitted box to its callback. Tables nested inside of
+    /// cells are imposed within their cell's fitted box.
+    ///
+    /// Callbacks are run even when the layout is over-constrained; the
+    /// returned error then describes how the layout (or any nested
+    /// table) was degraded to make do.
+    pub fn impose(&mut self, width: T, height: T) -> Result<(), Overconstrained<T>> {
+        let mut overflows: Vec<Overflow<T>> = Vec::new();
+        let placements = self.impose_at(T::zero(), T::zero(), width, height, &mut overflows);
+        if overflows.is_empty() {
+            Ok(())
+        } else {
+            Err(Overconstrained{overflows, placements})
+        }
+    }
+
+    /// Imposes the layout as though the table's top left corner were at
+    /// `x`, `y`; used to carry nested tables along with their cell.
+    /// Problems from this table and its nested tables are collected in
+    /// `overflows`.
+    fn impose_at(&mut self, x: T, y: T, width: T, height: T, overflows: &mut Vec<Overflow<T>>) -> Vec<Placement<T>> {
+        let placements = match self.solve(width, height) {
+            Ok(placements) => placements,
+            Err(mut e) => {
+                overflows.append(&mut e.overflows);
+                e.placements
+            }
+        };
+
+        let mut cells: Vec<&mut CellProperties<T>> = Vec::with_capacity(self.opcodes.len());
+        for op in &mut self.opcodes {
+            if let LayoutOp::Cell(cp) = op {
+                cells.push(cp);
+            }
+        }
+
+        // Run callbacks to impose layout.
+        for p in &placements {
+            let cp = &mut cells[p.index];
+            let (bx, by) = (x + p.content.x, y + p.content.y);
+            if let Some(cb) = &mut cp.callback {
+                (*cb)(bx, by, p.content.width, p.content.height);
+            }
+            if let Some(item) = &mut cp.item {
+                item.place(bx, by, p.content.width, p.content.height);
+            }
+            if let Some(table) = &mut cp.table {
+                table.impose_at(bx, by, p.content.width, p.content.height, overflows);
+            }
+        }
+
+        placements
+    }
+
+    /// Calculates the minimum, preferred and maximum size of the whole
+    /// table. This runs the measurement half of `impose`, then adds up
+    /// the sizes of every column and row along with the table's padding
+    /// and spacing. Useful for sizing windows or scroll areas to fit.
+    pub fn get_size_grouping(&self) -> SizeGrouping<T> {
+        let (total_rows, total_cols) = self.get_rows_cols();
+
+        let mut size = SizeGrouping{
+            minimum:   Size{width: T::zero(), height: T::zero()},
+            preferred: Size{width: T::zero(), height: T::zero()},
+            maximum:   Size{width: T::zero(), height: T::zero()},
+        }.inflate(&self.padding);
+        if total_cols == 0 {
+            size.maximum = Size{width: T::max_value(), height: T::max_value()};
+            return size
+        }
+
+        let gutters = Size{
+            width: self.horizontal_spacing * T::from_usize(total_cols - 1),
+            height: self.vertical_spacing * T::from_usize(total_rows - 1),
+        };
+        size.minimum = size.minimum.grow(&gutters);
+        size.preferred = size.preferred.grow(&gutters);
+        size.maximum = size.maximum.grow(&gutters);
+
+        // Cells whose height depends on their width are measured at
+        // the preferred width of their columns, but never below their
+        // minimum, as `distribute` would have it.
+        let mut m = self.measure(total_rows, total_cols, &mut |_| {});
+        let widths: Vec<T> = m.col_sizes.iter().map(|c| larger(c.preferred.width, c.minimum.width)).collect();
+        self.fit_heights(&mut m, &widths, &mut |_| {});
+        // Tracks are never laid out below their minimum, even when they
+        // prefer to be, so neither are they measured that way.
+        for c in &m.col_sizes {
+            size.minimum.width += c.minimum.width;
+            size.preferred.width += larger(c.preferred.width, c.minimum.width);
+            // maximums are often `max_value`, so keep from running off past it
+            size.maximum.width = size.maximum.width.saturating_add(c.maximum.width);
+        }
+        for r in &m.row_sizes {
+            size.minimum.height += r.minimum.height;
+            size.preferred.height += larger(r.preferred.height, r.minimum.height);
+            size.maximum.height = size.maximum.height.saturating_add(r.maximum.height);
+        }
+
+        size
+    }
+
+    /// Gathers size preferences for each column and row in the layout.
+    fn measure(&self, total_rows: usize, total_cols: usize, tracer: &mut dyn FnMut(&TraceEvent<T>)) -> Measurement<T> {
+        let slots = self.get_slots();
+
+        // Defaults are layered underneath every cell, and nested tables
+        // and layout items are measured up front, so they are only
+        // visited once.
+        let mut styles: Vec<CellProperties<T>> = Vec::with_capacity(slots.len());
+        let mut sizes: Vec<SizeGrouping<T>> = Vec::with_capacity(slots.len());
+        for slot in &slots {
+            if let LayoutOp::Cell(cp) = &self.opcodes[slot.op] {
+                let style = self.resolve(cp, slot.row, slot.col);
+                let mut size = style.size.clone();
+                if let Some(table) = &cp.table {
+                    size = SizeGrouping::join(&size, &table.get_size_grouping());
                 }
+                if let Some(item) = &cp.item {
+                    size = SizeGrouping::join(&size, &item.measure());
+                }
+                sizes.push(size);
+                styles.push(style);
             }
-        } else if error < 0.0 { // Not enough space; tense up some more!
-            let error = -error;
-            eprintln!("Error {}", error);
-            // We need to find slack space for each column
-            let mut total_slack: f32 = 0.0;
-            slack.clear();
-            slack.resize(total_cols as usize, 0.0);
-            for (i, x) in col_sizes.iter().map(|x| x.preferred.width - x.minimum.width).enumerate() {
-                slack[i] = x;
-                total_slack += x;
+        }
+
+        // Uniform cells all share the largest sizes found among them.
+        let mut uniform: Option<SizeGrouping<T>> = None;
+        for (style, size) in styles.iter().zip(&sizes) {
+            if style.flags.contains(CellFlags::Uniform) {
+                uniform = Some(match uniform {
+                    Some(u) => SizeGrouping::join_largest(&u, size),
+                    None => size.clone(),
+                });
             }
-            eprintln!("Total width slack: {}", total_slack);
+        }
+        if let Some(u) = &uniform {
+            for (style, size) in styles.iter().zip(&mut sizes) {
+                if style.flags.contains(CellFlags::Uniform) {
+                    *size = u.clone();
+                }
+            }
+        }
 
-            // XXX if error > total_slack, it is impossible to solve this constraint
-            // spread error across slack space, proportionate to this areas slack participation
-            for mut s in &mut slack {
-                let norm = *s / total_slack;
-                let error_over_slack = error * norm;
-                eprintln!("slack contribution {}", norm);
-                eprintln!("error over slack {}", error_over_slack);
-                *s -= error_over_slack
-            }
-
-            // Spread error across slack space.
-            for (i, x) in slack.iter().enumerate() {
-                col_sizes[i].preferred.width =
-                    f32::max(col_sizes[i].minimum.width + *x, 0.0);
-            }
-        }
-
-	// Calculate error along height distribution
-	let mut error = height;
-	for c in &row_sizes {
-            // Error is what remains once we have given each row its preferred size.
-            error -= c.preferred.height;
-	}
-
-        if error > 0.0 { // Extra space; relax the layout if we need to
-            // Figure out how many columns are expanding horizontally.
-            let expansions = has_yexpand.iter().filter(|y| **y).count();
-            if expansions > 0 {
-                let amount = error / expansions as f32;
-                for (i, e) in has_yexpand.iter().enumerate() {
-                    eprintln!("Expanding row {} = {}", i, e);
-                    if *e {
-                        row_sizes[i].preferred.height += amount;
-                    }
+        let mut col_sizes: Vec<SizeGrouping<T>> = Vec::with_capacity(total_cols);
+        // XXX resize_with is unstable, but would do what we want just fine
+        for _i in 0..total_cols {
+            col_sizes.push(Default::default());
+        }
+
+        // XXX resize_with is unstable, but would do what we want just fine
+        let mut row_sizes: Vec<SizeGrouping<T>> = Vec::with_capacity(total_rows);
+        for _i in 0..total_rows {
+            row_sizes.push(Default::default());
+        }
+
+        let mut x_weights: Vec<f32> = Vec::with_capacity(total_cols);
+        for _i in 0..total_cols {
+            x_weights.push(0.0);
+        }
+
+        let mut y_weights: Vec<f32> = Vec::with_capacity(total_rows);
+        for _i in 0..total_rows {
+            y_weights.push(0.0);
+        }
+
+        for ((slot, size), style) in slots.iter().zip(&sizes).zip(&styles) {
+            trace(tracer, TraceEvent::Measured{index: slot.cell, flags: style.flags});
+            let size = size.inflate(&style.padding);
+            let weight = clamp_weight(style.expand_weight);
+
+            // Spanning cells spread their size evenly over every row
+            // and column they occupy.
+            let tall = size.spread(T::from_usize(slot.rowspan));
+            for row in slot.row..slot.row+slot.rowspan {
+                // Tracks take on the heaviest weight among their expanding cells.
+                if style.flags.contains(CellFlags::ExpandVertical) {
+                    trace(tracer, TraceEvent::Flagged{axis: Axis::Vertical, track: row, weight});
+                    y_weights[row] = f32::max(y_weights[row], weight)
                 }
+                row_sizes[row] =
+                    SizeGrouping::join(&row_sizes[row], &tall);
             }
-        } else if error < 0.0 { // Not enough space; tense up some more!
-            let error = -error;
-            eprintln!("Error {}", error);
-            // We need to find slack space for each row
-            let mut total_slack: f32 = 0.0;
-            slack.clear();
-            slack.resize(total_rows as usize, 0.0);
-            for (i, y) in row_sizes.iter().map(|y| y.preferred.height - y.minimum.height).enumerate() {
-                slack[i] = y;
-                total_slack += y;
+
+            let midget = size.spread(T::from_usize(slot.colspan));
+            for col in slot.col..slot.col+slot.colspan {
+                if style.flags.contains(CellFlags::ExpandHorizontal) {
+                    trace(tracer, TraceEvent::Flagged{axis: Axis::Horizontal, track: col, weight});
+                    x_weights[col] = f32::max(x_weights[col], weight)
+                }
+                col_sizes[col] = SizeGrouping::join(&col_sizes[col], &midget);
             }
-            eprintln!("Total height slack: {}", total_slack);
+        }
 
-            // XXX if error > total_slack, it is impossible to solve this constraint
-            // spread error across slack space, proportionate to this areas slack participation
-            for mut s in &mut slack {
-                let norm = *s / total_slack;
-                let error_over_slack = error * norm;
-                eprintln!("slack contribution {}", norm);
-                eprintln!("error over slack {}", error_over_slack);
-                *s -= error_over_slack
+        Measurement{slots, styles, sizes, col_sizes, row_sizes, x_weights, y_weights}
+    }
+
+    /// Asks every cell with a height-for-width callback or layout item how
+    /// tall it is, given the width of each column in `widths`, then
+    /// gathers the row sizes again using the heights they answered with.
+    fn fit_heights(&self, m: &mut Measurement<T>, widths: &[T], tracer: &mut dyn FnMut(&TraceEvent<T>)) {
+        let mut refit = false;
+        for (i, slot) in m.slots.iter().enumerate() {
+            if let LayoutOp::Cell(cp) = &self.opcodes[slot.op] {
+                if cp.height_for_width.is_none() && cp.item.is_none() {
+                    continue
+                }
+
+                let mut width = self.horizontal_spacing * T::from_usize(slot.colspan - 1);
+                for w in &widths[slot.col..slot.col+slot.colspan] {
+                    width += *w;
+                }
+                let width = larger(width - m.styles[i].padding.horizontal(), T::zero());
+
+                // A closure on the cell has the final say over its item.
+                let height = match (&cp.height_for_width, &cp.item) {
+                    (Some(fun), _) => Some(fun(width)),
+                    (None, Some(item)) => item.height_for_width(width),
+                    (None, None) => None,
+                };
+                if let Some(height) = height {
+                    trace(tracer, TraceEvent::HeightForWidth{index: slot.cell, width, height});
+                    m.sizes[i].preferred.height = height;
+                    refit = true;
+                }
             }
+        }
+        if !refit {
+            return
+        }
 
-            // Spread error across slack space.
-            for (i, y) in slack.iter().enumerate() {
-                row_sizes[i].preferred.height =
-                    f32::max(row_sizes[i].minimum.height + *y, 0.0);
+        for r in &mut m.row_sizes {
+            *r = Default::default();
+        }
+        for ((slot, size), style) in m.slots.iter().zip(&m.sizes).zip(&m.styles) {
+            let tall = size.inflate(&style.padding).spread(T::from_usize(slot.rowspan));
+            for row in slot.row..slot.row+slot.rowspan {
+                m.row_sizes[row] = SizeGrouping::join(&m.row_sizes[row], &tall);
             }
         }
+    }
+
+    /// Lays the table out within the given width and height, returning
+    /// where each cell has been placed instead of invoking callbacks.
+    /// Cells spanning zero rows or columns are left out, and tables
+    /// nested inside of cells are not descended into.
+    ///
+    /// If the cells cannot shrink enough to fit, the layout is degraded
+    /// according to the table's `degradation` and an `Overconstrained`
+    /// error carries both the problem and the degraded placements.
+    pub fn solve(&self, width: T, height: T) -> Result<Vec<Placement<T>>, Overconstrained<T>> {
+        self.solve_traced(width, height, &mut |_| {})
+    }
+
+    /// Same as `solve`, but reports each step the solver takes to the
+    /// `tracer` as it goes. With the `log` feature enabled, every step is
+    /// also sent to the `log` facade at trace level.
+    pub fn solve_traced(&self, width: T, height: T, tracer: &mut dyn FnMut(&TraceEvent<T>)) -> Result<Vec<Placement<T>>, Overconstrained<T>> {
+        let Arrangement{placements, overflows, ..} = self.arrange(width, height, tracer);
+        if overflows.is_empty() {
+            Ok(placements)
+        } else {
+            Err(Overconstrained{overflows, placements})
+        }
+    }
+
+    /// Does the work of `solve_traced`, keeping hold of the columns, rows
+    /// and cell styles it settled on along the way.
+    fn arrange(&self, width: T, height: T, tracer: &mut dyn FnMut(&TraceEvent<T>)) -> Arrangement<T> {
+        let (total_rows, total_cols) = self.get_rows_cols();
+        if total_cols == 0 { // short-circuiting opportunity
+            return Arrangement{
+                columns: Vec::new(),
+                rows: Vec::new(),
+                styles: Vec::new(),
+                placements: Vec::new(),
+                overflows: Vec::new(),
+            }
+        }
+        trace(tracer, TraceEvent::Imposing{rows: total_rows, columns: total_cols});
+
+        let mut m = self.measure(total_rows, total_cols, tracer);
+
+        let mut overflows: Vec<Overflow<T>> = Vec::new();
+
+        // Padding and gutters are never handed out to columns or rows.
+        let available = width - self.padding.horizontal()
+            - self.horizontal_spacing * T::from_usize(total_cols - 1);
+        if let Some(o) = distribute(&mut m.col_sizes, &m.x_weights, Axis::Horizontal, available, self.degradation, self.integer_grid, tracer) {
+            overflows.push(o);
+        }
+
+        // Columns are settled, so cells whose height depends on their
+        // width can say how tall they are before rows are sized.
+        let widths: Vec<T> = m.col_sizes.iter().map(|c| c.preferred.width).collect();
+        self.fit_heights(&mut m, &widths, tracer);
+        let Measurement{slots, styles, sizes, col_sizes, mut row_sizes, y_weights, ..} = m;
+
+        let available = height - self.padding.vertical()
+            - self.vertical_spacing * T::from_usize(total_rows - 1);
+        if let Some(o) = distribute(&mut row_sizes, &y_weights, Axis::Vertical, available, self.degradation, self.integer_grid, tracer) {
+            overflows.push(o);
+        }
+
+        // Find where each column and row begins.
+        let mut col_offsets: Vec<T> = Vec::with_capacity(total_cols);
+        let mut x = self.padding.left;
+        for c in &col_sizes {
+            col_offsets.push(x);
+            x += c.preferred.width + self.horizontal_spacing;
+        }
+
+        let mut row_offsets: Vec<T> = Vec::with_capacity(total_rows);
+        let mut y = self.padding.top;
+        for r in &row_sizes {
+            row_offsets.push(y);
+            y += r.preferred.height + self.vertical_spacing;
+        }
 
         // Preparations complete. Now we pass the news along to our client.
-        let mut x = 0.0;
-        let mut y = 0.0;
-        row = 0;
-        col = 0;
-        for mut op in &mut self.opcodes {
-            // NB can probably make this mutable, and update it only when the row changes
-            let height = row_sizes[row as usize].preferred.height;
-            match op {
-                // Something that needs to be placed.
-                LayoutOp::Cell(cp) => match &cp.colspan {
-                    0 => {}, // Ignore this cell.
-                    _ => {
-                        let mut width: f32 = 0.0;
-                        for _i in 0..cp.colspan {
-                            width += col_sizes[col as usize].preferred.width;
-                            col += 1;
-                        }
-                        let s = Size{width, height};
-                        let (bx, by, bw, bh) = cp.size.box_fit(&s, cp.flags);
-
-                        // Run callback to impose layout.
-                        match &mut cp.callback {
-                            Some(cb) => {
-                                (*cb)(x+bx, y+by, bw, bh);
-                            }
-                            None => {},
-                        }
+        let mut placements: Vec<Placement<T>> = Vec::with_capacity(slots.len());
+        for ((slot, size), style) in slots.iter().zip(&sizes).zip(&styles) {
+            let x = col_offsets[slot.col];
+            let y = row_offsets[slot.row];
+
+            // Spanning cells also swallow the gutters between their tracks.
+            let mut width: T = self.horizontal_spacing * T::from_usize(slot.colspan - 1);
+            for c in &col_sizes[slot.col..slot.col+slot.colspan] {
+                width += c.preferred.width;
+            }
+
+            let mut height: T = self.vertical_spacing * T::from_usize(slot.rowspan - 1);
+            for r in &row_sizes[slot.row..slot.row+slot.rowspan] {
+                height += r.preferred.height;
+            }
+
+            // Contents are fitted to whatever is left inside the padding.
+            let s = Size{width, height}.deflate(&style.padding);
+            let (mut bx, mut by, mut bw, mut bh) = size.box_fit(&s, style.flags);
+            if self.integer_grid {
+                // Centering or fractional sizes would leave contents between units.
+                let floor = |v: T| T::from_f64(v.to_f64().floor());
+                bx = floor(bx);
+                by = floor(by);
+                bw = floor(bw);
+                bh = floor(bh);
+            }
+
+            let mut cell = Bounds{x, y, width, height};
+            let mut content = Bounds{
+                x: x + style.padding.left + bx,
+                y: y + style.padding.top + by,
+                width: bw,
+                height: bh,
+            };
+            if let Some(scale) = self.pixel_scale.filter(|s| *s > 0.0) {
+                // Take the far edges from where the last spanned track ends,
+                // so neighbouring cells snap to the very same edge.
+                let last_col = slot.col + slot.colspan - 1;
+                let last_row = slot.row + slot.rowspan - 1;
+                cell = snap(x, y,
+                            col_offsets[last_col] + col_sizes[last_col].preferred.width,
+                            row_offsets[last_row] + row_sizes[last_row].preferred.height,
+                            scale);
+                content = snap(content.x, content.y,
+                               content.x + content.width, content.y + content.height,
+                               scale);
+            }
+
+            placements.push(Placement{
+                index: slot.cell,
+                row: slot.row,
+                column: slot.col,
+                cell,
+                content,
+            });
+        }
+
+        Arrangement{
+            columns: col_offsets.into_iter().zip(col_sizes.iter().map(|c| c.preferred.width)).collect(),
+            rows: row_offsets.into_iter().zip(row_sizes.iter().map(|r| r.preferred.height)).collect(),
+            styles,
+            placements,
+            overflows,
+        }
+    }
+}
+
+/// Rounds the edges of a box to the nearest device pixel, `scale` being
+/// how many device pixels make up one unit, then measures the box
+/// between the rounded edges.
+fn snap<T: Scalar>(left: T, top: T, right: T, bottom: T, scale: f32) -> Bounds<T> {
+    let scale = f64::from(scale);
+    let round = |v: T| T::from_f64((v.to_f64() * scale).round() / scale);
+    let (left, top) = (round(left), round(top));
+    Bounds{x: left, y: top, width: round(right) - left, height: round(bottom) - top}
+}
 
-                        x += width;
+/// Hands out the difference between the space `available` and what the
+/// `tracks` prefer along one axis. Extra space goes to expanding tracks
+/// in proportion to their `weights`, up to their maximum sizes, while
+/// missing space is taken from each track's slack (how far it may
+/// shrink from its preferred size to its minimum size). Returns how the
+/// tracks were degraded if even their minimum sizes do not fit.
+///
+/// Space is worked out in `f64` whatever the scalar type, so shares are
+/// not rounded away as they are handed out. With `integer_grid` set the
+/// tracks are rounded to whole units before being converted back.
+fn distribute<T: Scalar>(tracks: &mut [SizeGrouping<T>], weights: &[f32], axis: Axis, available: T, degradation: Degradation, integer_grid: bool, tracer: &mut dyn FnMut(&TraceEvent<T>)) -> Option<Overflow<T>> {
+    let minimum: Vec<f64> = tracks.iter().map(|t| t.minimum.get(axis).to_f64()).collect();
+    let maximum: Vec<f64> = tracks.iter().map(|t| t.maximum.get(axis).to_f64()).collect();
+    // Tracks which prefer less than their minimum start at the minimum.
+    let mut sizes: Vec<f64> = tracks.iter().zip(&minimum)
+        .map(|(t, m)| f64::max(t.preferred.get(axis).to_f64(), *m))
+        .collect();
+    let available = available.to_f64();
+
+    // Error is what remains once we have given each track its preferred size.
+    let error = available - sizes.iter().sum::<f64>();
+    let mut overflow = None;
+
+    if error > 0.0 { // Extra space; relax the layout if we need to
+        // Only tracks with room left to grow below their maximum are
+        // candidates for expansion.
+        let mut candidates: Vec<bool> = weights.iter().enumerate()
+            .map(|(i, w)| *w > 0.0 && sizes[i] < maximum[i])
+            .collect();
+        let mut remaining = error;
+
+        // Tracks which hit their maximum give up whatever they could not
+        // take, and that surplus is handed around again to the others.
+        loop {
+            // Figure out how much weight the expanding tracks carry.
+            // Summed in f64, so a few very heavy tracks cannot overflow.
+            let total_weight: f64 = weights.iter().zip(&candidates)
+                .filter(|(_, c)| **c)
+                .map(|(w, _)| f64::from(*w))
+                .sum();
+            if total_weight <= 0.0 || remaining <= 0.0 {
+                break
+            }
+
+            let mut given = 0.0;
+            let mut clamped = false;
+            for (i, w) in weights.iter().enumerate() {
+                if !candidates[i] {
+                    continue
+                }
+
+                let room = maximum[i] - sizes[i];
+                let mut amount = remaining * f64::from(*w) / total_weight;
+                if amount >= room {
+                    trace(tracer, TraceEvent::Clamped{axis, track: i});
+                    amount = room;
+                    candidates[i] = false;
+                    clamped = true;
+                }
+
+                trace(tracer, TraceEvent::Expanded{axis, track: i, amount: T::from_f64(amount)});
+                sizes[i] += amount;
+                given += amount;
+            }
+
+            remaining -= given;
+            if !clamped {
+                break
+            }
+        }
+    } else if error < 0.0 { // Not enough space; tense up some more!
+        let error = -error;
+        // We need to find slack space for each track
+        let slack: Vec<f64> = sizes.iter().zip(&minimum)
+            .map(|(s, m)| f64::max(s - m, 0.0))
+            .collect();
+        let total_slack: f64 = slack.iter().sum();
+        trace(tracer, TraceEvent::Shrinking{axis, error: T::from_f64(error), total_slack: T::from_f64(total_slack)});
+
+        if error > total_slack {
+            // Even at their minimum sizes the tracks will not fit, so
+            // degrade the layout as the table asked us to.
+            match degradation {
+                Degradation::Overflow => {
+                    sizes.copy_from_slice(&minimum);
+                }
+                Degradation::Squash => {
+                    let total_minimum: f64 = minimum.iter().sum();
+                    let scale = if available > 0.0 { available / total_minimum } else { 0.0 };
+                    for (s, m) in sizes.iter_mut().zip(&minimum) {
+                        *s = m * scale;
                     }
-                },
-                // Increment to next row; reset placement cursors.
-                LayoutOp::Row => {
-                    x = 0.0;
-                    y += height;
-                    row += 1;
-                    col = 0;
                 }
             }
+            let o = Overflow{axis, amount: T::from_f64(error - total_slack), degradation};
+            trace(tracer, TraceEvent::Degraded(o.clone()));
+            overflow = Some(o);
+        } else {
+            // spread error across slack space, proportionate to this areas slack participation
+            for (i, s) in slack.iter().enumerate() {
+                let norm = s / total_slack;
+                let error_over_slack = error * norm;
+                trace(tracer, TraceEvent::Shrunk{axis, track: i, share: norm as f32, amount: T::from_f64(error_over_slack)});
+                sizes[i] = f64::max(minimum[i] + s - error_over_slack, 0.0);
+            }
+        }
+    }
+
+    if integer_grid {
+        round_to_grid(&mut sizes);
+    }
+    for (t, s) in tracks.iter_mut().zip(&sizes) {
+        *t.preferred.get_mut(axis) = T::from_f64(*s);
+    }
+
+    overflow
+}
+
+/// Rounds every size down to a whole unit, then hands the units lost to
+/// rounding back out one at a time, to the sizes which lost the largest
+/// fractions first (earlier sizes win ties). The total is kept, rounded
+/// to the nearest whole unit.
+fn round_to_grid(sizes: &mut [f64]) {
+    let total = sizes.iter().sum::<f64>().round();
+    let mut order: Vec<usize> = (0..sizes.len()).collect();
+    // stable sort, so ties stay in track order
+    order.sort_by(|a, b| {
+        let fa = sizes[*a] - sizes[*a].floor();
+        let fb = sizes[*b] - sizes[*b].floor();
+        fb.partial_cmp(&fa).unwrap_or(Ordering::Equal)
+    });
+
+    for s in sizes.iter_mut() {
+        *s = s.floor();
+    }
+    let mut leftover = total - sizes.iter().sum::<f64>();
+    for i in order {
+        if leftover < 1.0 {
+            break
         }
+        sizes[i] += 1.0;
+        leftover -= 1.0;
     }
 }
 
 
 #[cfg(test)]
 mod test {
-    extern crate test;
-
     use ::*;
     #[test]
     fn expanding_layout() {
@@ -681,7 +1938,7 @@ mod test {
                         .anchor_bottom()
                         .fill_horizontal()
                         .preferred_size(Size{width: 64.0, height: 64.0}));
-        engine.impose(320.0, 240.0);
+        engine.impose(320.0, 240.0).unwrap();
     }
 
     #[test]
@@ -716,7 +1973,7 @@ mod test {
                         }))
                         .colspan(2)
                         .preferred_size(Size{width: 64.0, height: 64.0}));
-        engine.impose(32.0, 32.0);
+        engine.impose(32.0, 32.0).unwrap();
     }
 
     #[test]
@@ -734,36 +1991,971 @@ mod test {
                         .anchor_vertical_center()
                         .expand()
                         .preferred_size(Size{width: 32.0, height: 32.0}));
-        engine.impose(64.0, 64.0);
+        engine.impose(64.0, 64.0).unwrap();
+    }
+
+    #[test]
+    fn rowspan_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 0.0);
+                            assert_eq!(y, 0.0);
+                            assert_eq!(w, 32.0);
+                            assert_eq!(h, 64.0);
+                        }))
+                        .rowspan(2)
+                        .fill()
+                        .preferred_size(Size{width: 32.0, height: 64.0}));
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 32.0);
+                            assert_eq!(y, 0.0);
+                            assert_eq!(w, 32.0);
+                            assert_eq!(h, 32.0);
+                        }))
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.with_row();
+        // first slot of this row is taken by the spanning cell, so
+        // this lands in the second column
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 32.0);
+                            assert_eq!(y, 32.0);
+                            assert_eq!(w, 32.0);
+                            assert_eq!(h, 32.0);
+                        }))
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        assert_eq!(engine.get_rows_cols(), (2, 2));
+        engine.impose(64.0, 64.0).unwrap();
+    }
+
+    #[test]
+    fn colspan_under_rowspan_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .preferred_size(Size{width: 10.0, height: 10.0}));
+        engine.with_cell(CellProperties::new()
+                        .rowspan(2)
+                        .preferred_size(Size{width: 10.0, height: 20.0}));
+        engine.with_row();
+        // the second column is still taken by the spanning cell, so a cell
+        // spanning two columns cannot start in the first one either
+        engine.with_cell(CellProperties::new()
+                        .colspan(2)
+                        .preferred_size(Size{width: 20.0, height: 10.0}));
+        assert_eq!(engine.get_rows_cols(), (2, 4));
+
+        let placements = engine.solve(40.0, 20.0).unwrap();
+        assert_eq!((placements[1].row, placements[1].column), (0, 1));
+        assert_eq!(placements[1].cell, Bounds{x: 10.0, y: 0.0, width: 10.0, height: 20.0});
+        assert_eq!((placements[2].row, placements[2].column), (1, 2));
+        assert_eq!(placements[2].cell, Bounds{x: 20.0, y: 10.0, width: 20.0, height: 10.0});
+    }
+
+    #[test]
+    fn uniform_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 0.0);
+                            assert_eq!(y, 0.0);
+                            assert_eq!(w, 64.0);
+                            assert_eq!(h, 24.0);
+                        }))
+                        .uniform()
+                        .preferred_size(Size{width: 32.0, height: 24.0}));
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            // takes the width of its wider sibling, while
+                            // the sibling takes on this one's height
+                            assert_eq!(x, 64.0);
+                            assert_eq!(y, 0.0);
+                            assert_eq!(w, 64.0);
+                            assert_eq!(h, 24.0);
+                        }))
+                        .uniform()
+                        .preferred_size(Size{width: 64.0, height: 16.0}));
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            // not uniform, so keeps its own size
+                            assert_eq!(x, 128.0);
+                            assert_eq!(y, 0.0);
+                            assert_eq!(w, 16.0);
+                            assert_eq!(h, 16.0);
+                        }))
+                        .preferred_size(Size{width: 16.0, height: 16.0}));
+        engine.impose(320.0, 240.0).unwrap();
+    }
+
+    #[test]
+    fn padded_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 4.0);
+                            assert_eq!(y, 2.0);
+                            assert_eq!(w, 32.0);
+                            assert_eq!(h, 32.0);
+                        }))
+                        .padding(Rectangle{top: 2.0, left: 4.0, bottom: 6.0, right: 8.0})
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            // previous column is as wide as its padded contents
+                            assert_eq!(x, 44.0 + 1.0);
+                            assert_eq!(y, 1.0);
+                            // fills what is left after padding
+                            assert_eq!(w, 64.0 - 44.0 - 2.0);
+                            assert_eq!(h, 40.0 - 2.0);
+                        }))
+                        .padding(Rectangle{top: 1.0, left: 1.0, bottom: 1.0, right: 1.0})
+                        .expand()
+                        .fill()
+                        .preferred_size(Size{width: 8.0, height: 8.0}));
+        engine.impose(64.0, 40.0).unwrap();
     }
 
-    #[bench]
-    fn impose2x3(b: &mut test::Bencher) {
-        // We only test the speed of layout calculation here, not
-        // the overhead of communicating the layout back to the client.
+    #[test]
+    fn spaced_layout() {
         let mut engine = TableLayout::new();
+        engine.with_padding(Rectangle{top: 4.0, left: 8.0, bottom: 4.0, right: 8.0})
+              .with_spacing(16.0, 8.0);
         engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 8.0);
+                            assert_eq!(y, 4.0);
+                            assert_eq!(w, 32.0);
+                            assert_eq!(h, 32.0);
+                        }))
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 8.0 + 32.0 + 16.0);
+                            assert_eq!(y, 4.0);
+                            // everything left over once padding and
+                            // spacing have been taken out
+                            assert_eq!(w, 128.0 - 16.0 - 16.0 - 32.0);
+                            assert_eq!(h, 32.0);
+                        }))
+                        .expand_horizontal()
+                        .fill_horizontal()
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.with_row();
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 8.0);
+                            assert_eq!(y, 4.0 + 32.0 + 8.0);
+                            // spans the gutter between both columns
+                            assert_eq!(w, 128.0 - 16.0);
+                            assert_eq!(h, 96.0 - 8.0 - 8.0 - 32.0);
+                        }))
+                        .colspan(2)
+                        .expand_vertical()
+                        .fill()
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.impose(128.0, 96.0).unwrap();
+    }
+
+    #[test]
+    fn solved_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .padding(Rectangle{top: 2.0, left: 2.0, bottom: 2.0, right: 2.0})
+                        .preferred_size(Size{width: 28.0, height: 28.0}));
+        engine.with_cell(CellProperties::new()
+                        .colspan(0));
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
                         .anchor_right()
+                        .preferred_size(Size{width: 16.0, height: 16.0}));
+        let placements = engine.solve(64.0, 32.0).unwrap();
+        assert_eq!(placements, vec![
+            Placement{
+                index: 0,
+                row: 0,
+                column: 0,
+                cell: Bounds{x: 0.0, y: 0.0, width: 32.0, height: 32.0},
+                content: Bounds{x: 2.0, y: 2.0, width: 28.0, height: 28.0},
+            },
+            // the cell spanning nothing is skipped, but still counted
+            Placement{
+                index: 2,
+                row: 0,
+                column: 1,
+                cell: Bounds{x: 32.0, y: 0.0, width: 32.0, height: 32.0},
+                content: Bounds{x: 48.0, y: 0.0, width: 16.0, height: 16.0},
+            },
+        ]);
+    }
+
+    #[test]
+    fn nested_layout() {
+        let mut inner = TableLayout::new();
+        inner.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 32.0);
+                            assert_eq!(y, 16.0);
+                            assert_eq!(w, 16.0);
+                            assert_eq!(h, 16.0);
+                        }))
+                        .preferred_size(Size{width: 16.0, height: 16.0}));
+        inner.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 48.0);
+                            assert_eq!(y, 16.0);
+                            assert_eq!(w, 16.0);
+                            assert_eq!(h, 16.0);
+                        }))
+                        .preferred_size(Size{width: 16.0, height: 16.0}));
+
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        // the outer column is sized after what the inner table prefers,
+        // and the inner table moves along with the fitted box
+        engine.with_cell(CellProperties::new()
                         .anchor_bottom()
-                        .preferred_size(Size{width: 64.0, height: 64.0}));
+                        .table(inner));
+        let placements = engine.solve(64.0, 32.0).unwrap();
+        assert_eq!(placements[1].cell, Bounds{x: 32.0, y: 0.0, width: 32.0, height: 32.0});
+        engine.impose(64.0, 32.0).unwrap();
+    }
+
+    #[test]
+    fn measured_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_padding(Rectangle{top: 1.0, left: 2.0, bottom: 3.0, right: 4.0})
+              .with_spacing(8.0, 4.0);
+        engine.with_cell(CellProperties::new()
+                        .minimum_size(Size{width: 16.0, height: 16.0})
+                        .maximum_size(Size{width: 64.0, height: 64.0})
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.with_cell(CellProperties::new()
+                        .maximum_size(Size{width: 64.0, height: 64.0})
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.with_row();
+        // spread evenly over both columns, making each of them wider
+        engine.with_cell(CellProperties::new()
+                        .colspan(2)
+                        .minimum_size(Size{width: 40.0, height: 8.0})
+                        .maximum_size(Size{width: 128.0, height: 16.0})
+                        .preferred_size(Size{width: 100.0, height: 10.0}));
+
+        let size = engine.get_size_grouping();
+        assert_eq!(size.minimum.width, 6.0 + 8.0 + 20.0 + 20.0);
+        assert_eq!(size.minimum.height, 4.0 + 4.0 + 16.0 + 8.0);
+        assert_eq!(size.preferred.width, 6.0 + 8.0 + 50.0 + 50.0);
+        assert_eq!(size.preferred.height, 4.0 + 4.0 + 32.0 + 10.0);
+        assert_eq!(size.maximum.width, 6.0 + 8.0 + 64.0 + 64.0);
+        assert_eq!(size.maximum.height, 4.0 + 4.0 + 64.0 + 16.0);
+
+        // an unbounded cell leaves the whole table unbounded
+        engine.with_cell(CellProperties::new());
+        assert_eq!(engine.get_size_grouping().maximum.width, f32::MAX);
+
+        let empty = TableLayout::new().get_size_grouping();
+        assert_eq!(empty.preferred.width, 0.0);
+        assert_eq!(empty.maximum.height, f32::MAX);
+
+        // a cell with only a minimum size prefers to be at least that big,
+        // so the table fits within its own preferred size
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .minimum_size(Size{width: 50.0, height: 10.0}));
+        let size = engine.get_size_grouping();
+        assert_eq!(size.minimum.width, 50.0);
+        assert!(size.preferred.width >= size.minimum.width);
+        assert!(size.preferred.height >= size.minimum.height);
+        assert!(engine.solve(size.preferred.width, size.preferred.height).is_ok());
+    }
+
+    #[test]
+    fn overconstrained_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .minimum_size(Size{width: 32.0, height: 16.0})
+                        .preferred_size(Size{width: 32.0, height: 16.0}));
+        engine.with_cell(CellProperties::new()
+                        .minimum_size(Size{width: 32.0, height: 16.0})
+                        .preferred_size(Size{width: 48.0, height: 16.0}));
+
+        // no slack at all vertically, which used to divide by zero
+        let e = engine.solve(48.0, 8.0).unwrap_err();
+        assert_eq!(e.overflows, vec![
+            Overflow{axis: Axis::Horizontal, amount: 16.0, degradation: Degradation::Overflow},
+            Overflow{axis: Axis::Vertical, amount: 8.0, degradation: Degradation::Overflow},
+        ]);
+        // cells keep their minimum sizes and run past the edges
+        assert_eq!(e.placements[0].cell, Bounds{x: 0.0, y: 0.0, width: 32.0, height: 16.0});
+        assert_eq!(e.placements[1].cell, Bounds{x: 32.0, y: 0.0, width: 32.0, height: 16.0});
+
+        engine.with_degradation(Degradation::Squash);
+        let e = engine.solve(48.0, 16.0).unwrap_err();
+        assert_eq!(e.overflows, vec![
+            Overflow{axis: Axis::Horizontal, amount: 16.0, degradation: Degradation::Squash},
+        ]);
+        // cells are shrunk below their minimums to fit exactly
+        assert_eq!(e.placements[0].cell, Bounds{x: 0.0, y: 0.0, width: 24.0, height: 16.0});
+        assert_eq!(e.placements[1].cell, Bounds{x: 24.0, y: 0.0, width: 24.0, height: 16.0});
+
+        // shrinking into the available slack is not a problem
+        assert!(engine.solve(72.0, 16.0).is_ok());
+
+        // tracks are never smaller than their minimums, even when they
+        // prefer to be
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .minimum_size(Size{width: 50.0, height: 10.0}));
+        engine.with_cell(CellProperties::new()
+                        .minimum_size(Size{width: 80.0, height: 10.0}));
+        let e = engine.solve(100.0, 10.0).unwrap_err();
+        assert_eq!(e.overflows, vec![
+            Overflow{axis: Axis::Horizontal, amount: 30.0, degradation: Degradation::Overflow},
+        ]);
+        assert_eq!(e.placements[1].cell, Bounds{x: 50.0, y: 0.0, width: 80.0, height: 10.0});
+        assert!(engine.solve(130.0, 10.0).is_ok());
+    }
+
+    #[test]
+    fn weighted_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 0.0);
+                            assert_eq!(w, 16.0 + 25.0);
+                            assert_eq!(h, 16.0);
+                        }))
+                        .expand_horizontal()
+                        .fill_horizontal()
+                        .preferred_size(Size{width: 16.0, height: 16.0}));
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 16.0 + 25.0);
+                            assert_eq!(w, 16.0 + 75.0);
+                            // only the first row expands, so it takes
+                            // all of the extra height regardless of weight
+                            assert_eq!(h, 80.0);
+                        }))
+                        .expand()
+                        .expand_weight(3.0)
+                        .fill()
+                        .preferred_size(Size{width: 16.0, height: 16.0}));
+        engine.with_row();
+        // the lighter cell does not dilute the column's weight, and
+        // a cell which does not expand carries no weight at all
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
+                        .expand_weight(0.5));
+        engine.with_cell(CellProperties::new()
+                        .expand_weight(10.0));
+        engine.impose(132.0, 80.0).unwrap();
+    }
+
+    #[test]
+    fn unbounded_weight_layout() {
+        assert_eq!(CellProperties::new().expand_weight(-2.0).expand_weight, 0.0);
+        assert_eq!(CellProperties::new().expand_weight(f32::NAN).expand_weight, 0.0);
+        assert_eq!(CellProperties::new().expand_weight(f32::INFINITY).expand_weight, f32::MAX);
+
+        // neither infinite nor enormous weights may turn sizes into NaN
+        for weight in &[f32::INFINITY, f32::MAX] {
+            let mut engine = TableLayout::new();
+            engine.with_cell(CellProperties::new()
+                            .expand_horizontal()
+                            .expand_weight(*weight));
+            engine.with_cell(CellProperties::new()
+                            .expand_horizontal()
+                            .expand_weight(*weight));
+            engine.with_cell(CellProperties::new()
+                            .expand_horizontal());
+            let placements = engine.solve(100.0, 10.0).unwrap();
+            assert_eq!(placements[0].cell.width, 50.0);
+            assert_eq!(placements[1].cell.width, 50.0);
+            assert!(placements[2].cell.width < 0.001);
+        }
+
+        // weights set on the public field are kept in check as well
+        let mut engine = TableLayout::new();
+        let mut cell = CellProperties::new().expand_horizontal();
+        cell.expand_weight = f32::INFINITY;
+        engine.with_cell(cell);
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
+                        .expand_weight(f32::NEG_INFINITY));
+        let placements = engine.solve(100.0, 10.0).unwrap();
+        assert_eq!(placements[0].cell.width, 100.0);
+        assert_eq!(placements[1].cell.width, 0.0);
+    }
+
+    #[test]
+    fn clamped_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
+                        .maximum_size(Size{width: 20.0, height: 20.0})
+                        .preferred_size(Size{width: 10.0, height: 10.0}));
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
+                        .preferred_size(Size{width: 10.0, height: 10.0}));
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
+                        .preferred_size(Size{width: 10.0, height: 10.0}));
+
+        // a third of 90 each, but the first column stops at 20 and the
+        // rest of its share goes to the other two
+        let placements = engine.solve(120.0, 10.0).unwrap();
+        assert_eq!(placements[0].cell, Bounds{x: 0.0, y: 0.0, width: 20.0, height: 10.0});
+        assert_eq!(placements[1].cell, Bounds{x: 20.0, y: 0.0, width: 50.0, height: 10.0});
+        assert_eq!(placements[2].cell, Bounds{x: 70.0, y: 0.0, width: 50.0, height: 10.0});
+
+        // once every column is at its maximum, the surplus goes unused
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
+                        .maximum_size(Size{width: 20.0, height: 20.0})
+                        .preferred_size(Size{width: 10.0, height: 10.0}));
+        let placements = engine.solve(120.0, 10.0).unwrap();
+        assert_eq!(placements[0].cell, Bounds{x: 0.0, y: 0.0, width: 20.0, height: 10.0});
+    }
+
+    #[test]
+    fn box_fit_matrix() {
+        let size = SizeGrouping{
+            minimum:   Size{width: 10.0, height: 5.0},
+            preferred: Size{width: 20.0, height: 10.0},
+            maximum:   Size{width: 60.0, height: 30.0},
+        };
+        let area = Size{width: 100.0, height: 50.0};
+
+        let anchors = [
+            CellFlags::AnchorLeft, CellFlags::AnchorRight, CellFlags::AnchorHorizontalCenter,
+            CellFlags::AnchorTop, CellFlags::AnchorBottom, CellFlags::AnchorVerticalCenter,
+        ];
+        let fills = [
+            CellFlags::None, CellFlags::FillHorizontal,
+            CellFlags::FillVertical, CellFlags::FillHorizontal | CellFlags::FillVertical,
+        ];
+
+        for bits in 0..(1 << anchors.len()) {
+            let mut flags = CellFlags::None;
+            for (i, a) in anchors.iter().enumerate() {
+                if bits & (1 << i) != 0 {
+                    flags |= *a;
+                }
+            }
+
+            for fill in &fills {
+                let flags = flags | *fill;
+                let (x, y, w, h) = size.box_fit(&area, flags);
+
+                // fills grow to the maximum size, otherwise stick to preferred
+                let ew = if flags.contains(CellFlags::FillHorizontal) { 60.0 } else { 20.0 };
+                let eh = if flags.contains(CellFlags::FillVertical) { 30.0 } else { 10.0 };
+
+                // (start, end, center) => where the box should go
+                let ex = match (flags.contains(CellFlags::AnchorLeft),
+                                flags.contains(CellFlags::AnchorRight),
+                                flags.contains(CellFlags::AnchorHorizontalCenter)) {
+                    (_, _, true)          => (100.0 - ew) / 2.0,
+                    (true, true, false)   => (100.0 - ew) / 2.0,
+                    (false, true, false)  => 100.0 - ew,
+                    (true, false, false)  => 0.0,
+                    (false, false, false) => 0.0,
+                };
+                let ey = match (flags.contains(CellFlags::AnchorTop),
+                                flags.contains(CellFlags::AnchorBottom),
+                                flags.contains(CellFlags::AnchorVerticalCenter)) {
+                    (_, _, true)          => (50.0 - eh) / 2.0,
+                    (true, true, false)   => (50.0 - eh) / 2.0,
+                    (false, true, false)  => 50.0 - eh,
+                    (true, false, false)  => 0.0,
+                    (false, false, false) => 0.0,
+                };
+
+                assert_eq!((x, y, w, h), (ex, ey, ew, eh), "{:?}", flags);
+            }
+        }
+
+        // boxes never grow past the area they are fitted to
+        let small = Size{width: 15.0, height: 8.0};
+        assert_eq!(size.box_fit(&small, CellFlags::FillHorizontal | CellFlags::AnchorRight),
+                   (0.0, 0.0, 15.0, 8.0));
+    }
+
+    #[test]
+    fn vertically_centered_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .callback(Box::new(|x, y, w, h| {
+                            println!("{} {} {} {}", x, y, w, h);
+                            assert_eq!(x, 0.0);
+                            assert_eq!(y, 16.0);
+                            assert_eq!(w, 32.0);
+                            assert_eq!(h, 32.0);
+                        }))
+                        .anchor_vertical_center()
+                        .expand()
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.impose(64.0, 64.0).unwrap();
+    }
+
+    #[test]
+    fn layered_defaults() {
+        let mut engine = TableLayout::new();
+        engine.with_cell_defaults(CellProperties::new()
+                                  .padding(Rectangle{top: 1.0, left: 1.0, bottom: 1.0, right: 1.0})
+                                  .preferred_size(Size{width: 8.0, height: 8.0}));
+        engine.with_row_defaults(1, CellProperties::new()
+                                 .anchor_bottom()
+                                 .preferred_size(Size{width: 12.0, height: 12.0}));
+        engine.with_column_defaults(1, CellProperties::new()
+                                    .anchor_right()
+                                    .minimum_size(Size{width: 4.0, height: 4.0})
+                                    .preferred_size(Size{width: 16.0, height: 16.0}));
+
+        engine.with_cell(CellProperties::new());
+        engine.with_cell(CellProperties::new());
+        engine.with_row();
+        engine.with_cell(CellProperties::new());
+        // the cell's own preferred size and anchor win over its row and
+        // column, but it still inherits their other properties
         engine.with_cell(CellProperties::new()
-                        .anchor_top()
                         .anchor_left()
+                        .preferred_size(Size{width: 6.0, height: 6.0}));
+
+        let placements = engine.solve(100.0, 100.0).unwrap();
+        // table defaults only
+        assert_eq!(placements[0].content, Bounds{x: 1.0, y: 1.0, width: 8.0, height: 8.0});
+        // column defaults on top of table defaults
+        assert_eq!(placements[1].cell, Bounds{x: 14.0, y: 0.0, width: 18.0, height: 18.0});
+        assert_eq!(placements[1].content, Bounds{x: 15.0, y: 1.0, width: 16.0, height: 16.0});
+        // row defaults on top of table defaults
+        assert_eq!(placements[2].content, Bounds{x: 1.0, y: 19.0, width: 12.0, height: 12.0});
+        // anchored bottom by the row, left by itself, padded by the table
+        assert_eq!(placements[3].cell, Bounds{x: 14.0, y: 18.0, width: 18.0, height: 14.0});
+        assert_eq!(placements[3].content, Bounds{x: 15.0, y: 25.0, width: 6.0, height: 6.0});
+
+        // defaults are applied at impose time, so changing them restyles
+        // cells which have already been added
+        engine.with_column_defaults(1, CellProperties::new()
+                                    .preferred_size(Size{width: 32.0, height: 32.0}));
+        let placements = engine.solve(100.0, 100.0).unwrap();
+        assert_eq!(placements[1].content, Bounds{x: 15.0, y: 1.0, width: 32.0, height: 32.0});
+    }
+
+    #[test]
+    fn layered_fields() {
+        let mut engine = TableLayout::new();
+        engine.with_cell_defaults(CellProperties::new()
+                                  .expand()
+                                  .fill()
+                                  .preferred_size(Size{width: 8.0, height: 8.0}));
+
+        // cells built without the builder methods keep their own values
+        let mut cell = CellProperties::new();
+        cell.size.preferred = Size{width: 40.0, height: 20.0};
+        engine.with_cell(cell);
+        engine.with_cell(CellProperties{
+            size: SizeGrouping{
+                minimum:   Size{width: 0.0, height: 0.0},
+                preferred: Size{width: 30.0, height: 0.0},
+                maximum:   Size{width: 30.0, height: f32::MAX},
+            },
+            ..Default::default()
+        });
+        // flags from the defaults may be turned off again
+        engine.with_cell(CellProperties::new()
+                        .unset(CellFlags::ExpandHorizontal | CellFlags::FillVertical));
+
+        let placements = engine.solve(100.0, 20.0).unwrap();
+        assert_eq!(placements[0].cell, Bounds{x: 0.0, y: 0.0, width: 62.0, height: 20.0});
+        assert_eq!(placements[0].content, Bounds{x: 0.0, y: 0.0, width: 62.0, height: 20.0});
+        // only the width differs from the default, so the height is inherited
+        assert_eq!(placements[1].cell, Bounds{x: 62.0, y: 0.0, width: 30.0, height: 20.0});
+        assert_eq!(placements[1].content, Bounds{x: 62.0, y: 0.0, width: 30.0, height: 20.0});
+        assert_eq!(placements[2].cell, Bounds{x: 92.0, y: 0.0, width: 8.0, height: 20.0});
+        assert_eq!(placements[2].content, Bounds{x: 92.0, y: 0.0, width: 8.0, height: 8.0});
+    }
+
+    #[test]
+    fn large_layout() {
+        let mut engine = TableLayout::new();
+        for _i in 0..1000 {
+            engine.with_cell(CellProperties::new()
+                            .preferred_size(Size{width: 1.0, height: 1.0}));
+        }
+        assert_eq!(engine.column, 1000);
+        engine.with_row();
+        engine.with_cell(CellProperties::new()
+                        .colspan(1000)
+                        .rowspan(300)
+                        .preferred_size(Size{width: 1000.0, height: 300.0}));
+        engine.with_row_defaults(256, CellProperties::new().anchor_right());
+        engine.with_column_defaults(999, CellProperties::new().expand_horizontal());
+        assert_eq!(engine.get_rows_cols(), (301, 1000));
+
+        let placements = engine.solve(1001.0, 301.0).unwrap();
+        assert_eq!(placements.len(), 1001);
+        assert_eq!(placements[999].column, 999);
+        assert_eq!(placements[999].cell, Bounds{x: 999.0, y: 0.0, width: 2.0, height: 1.0});
+        assert_eq!(placements[1000].row, 1);
+        assert_eq!(placements[1000].cell, Bounds{x: 0.0, y: 1.0, width: 1001.0, height: 300.0});
+    }
+
+    #[test]
+    fn traced_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
+                        .preferred_size(Size{width: 32.0, height: 32.0}));
+        engine.with_cell(CellProperties::new()
+                        .preferred_size(Size{width: 32.0, height: 48.0}));
+
+        let mut events: Vec<TraceEvent> = Vec::new();
+        engine.solve_traced(96.0, 16.0, &mut |e| events.push(e.clone())).unwrap();
+        assert_eq!(events, vec![
+            TraceEvent::Imposing{rows: 1, columns: 2},
+            TraceEvent::Measured{index: 0, flags: CellFlags::ExpandHorizontal},
+            TraceEvent::Flagged{axis: Axis::Horizontal, track: 0, weight: 1.0},
+            TraceEvent::Measured{index: 1, flags: CellFlags::None},
+            TraceEvent::Expanded{axis: Axis::Horizontal, track: 0, amount: 32.0},
+            TraceEvent::Shrinking{axis: Axis::Vertical, error: 32.0, total_slack: 48.0},
+            TraceEvent::Shrunk{axis: Axis::Vertical, track: 0, share: 1.0, amount: 32.0},
+        ]);
+    }
+
+    #[test]
+    fn scalar_layout() {
+        // double precision, for when f32 runs out of digits
+        let mut engine: TableLayout<f64> = Default::default();
+        engine.with_cell(CellProperties::default()
+                        .preferred_size(Size{width: 64.0, height: 64.0}));
+        engine.with_cell(CellProperties::default()
                         .expand_horizontal()
+                        .fill()
                         .preferred_size(Size{width: 64.0, height: 64.0}));
+        let placements = engine.solve(320.0, 64.0).unwrap();
+        assert_eq!(placements[1].content, Bounds{x: 64.0, y: 0.0, width: 256.0, height: 64.0});
+
+        // whole numbers, for character cells
+        let mut engine: TableLayout<i32> = Default::default();
+        engine.with_padding(Rectangle{top: 2, left: 2, bottom: 2, right: 2});
+        engine.with_cell(CellProperties::default()
+                        .expand()
+                        .anchor_center()
+                        .preferred_size(Size{width: 10, height: 4})
+                        .callback(Box::new(|x, y, w, h| {
+                            assert_eq!((x, y, w, h), (15, 8, 10, 4));
+                        })));
+        engine.impose(40, 20).unwrap();
+
+        // unbounded maximums saturate rather than overflowing
+        assert_eq!(engine.get_size_grouping().maximum.width, i32::MAX);
+    }
+
+    #[test]
+    fn integer_grid_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_integer_grid(true);
+        for _i in 0..3 {
+            engine.with_cell(CellProperties::new()
+                            .expand()
+                            .anchor_vertical_center()
+                            .preferred_size(Size{width: 10.0, height: 10.0}));
+        }
+
+        // 320 / 3 leaves two units over, which go to the first two columns
+        let placements = engine.solve(320.0, 25.0).unwrap();
+        assert_eq!(placements[0].cell, Bounds{x: 0.0, y: 0.0, width: 107.0, height: 25.0});
+        assert_eq!(placements[1].cell, Bounds{x: 107.0, y: 0.0, width: 107.0, height: 25.0});
+        assert_eq!(placements[2].cell, Bounds{x: 214.0, y: 0.0, width: 106.0, height: 25.0});
+        // centering 10 within 25 rounds down
+        assert_eq!(placements[2].content, Bounds{x: 214.0, y: 7.0, width: 10.0, height: 10.0});
+
+        // integer scalars would otherwise lose the leftover units
+        let mut engine: TableLayout<i32> = Default::default();
+        engine.with_integer_grid(true);
+        engine.with_cell(CellProperties::default()
+                        .expand_horizontal()
+                        .preferred_size(Size{width: 0, height: 1}));
+        engine.with_cell(CellProperties::default()
+                        .expand_horizontal()
+                        .expand_weight(2.0)
+                        .preferred_size(Size{width: 0, height: 1}));
+        let placements = engine.solve(80, 1).unwrap();
+        assert_eq!(placements[0].cell, Bounds{x: 0, y: 0, width: 27, height: 1});
+        assert_eq!(placements[1].cell, Bounds{x: 27, y: 0, width: 53, height: 1});
+
+        // shrinking keeps to whole units as well
+        let mut engine = TableLayout::new();
+        engine.with_integer_grid(true);
+        for _i in 0..3 {
+            engine.with_cell(CellProperties::new()
+                            .minimum_size(Size{width: 0.0, height: 0.0})
+                            .preferred_size(Size{width: 10.0, height: 10.0}));
+        }
+        let placements = engine.solve(20.0, 10.0).unwrap();
+        let widths: Vec<f32> = placements.iter().map(|p| p.cell.width).collect();
+        assert_eq!(widths, vec![7.0, 7.0, 6.0]);
+    }
+
+    #[test]
+    fn snapped_layout() {
+        let mut engine = TableLayout::new();
+        for _i in 0..3 {
+            engine.with_cell(CellProperties::new()
+                            .expand()
+                            .fill()
+                            .preferred_size(Size{width: 10.0, height: 10.0}));
+        }
+        engine.with_row();
+        engine.with_cell(CellProperties::new()
+                        .preferred_size(Size{width: 10.3, height: 10.3}));
+        engine.with_cell(CellProperties::new()
+                        .colspan(2)
+                        .fill()
+                        .preferred_size(Size{width: 10.0, height: 10.0}));
+
+        // edges land on whole pixels, and neighbours share them
+        engine.with_pixel_snapping(Some(1.0));
+        let placements = engine.solve(100.0, 50.0).unwrap();
+        let columns: Vec<(f32, f32)> = placements[0..3].iter()
+            .map(|p| (p.cell.x, p.cell.width))
+            .collect();
+        assert_eq!(columns, vec![(0.0, 34.0), (34.0, 33.0), (67.0, 33.0)]);
+        assert_eq!(placements[2].content, placements[2].cell);
+        assert_eq!(placements[3].content, Bounds{x: 0.0, y: 40.0, width: 10.0, height: 10.0});
+        assert_eq!(placements[4].cell, Bounds{x: 34.0, y: 40.0, width: 66.0, height: 10.0});
+
+        // two device pixels to the unit allows half units
+        engine.with_pixel_snapping(Some(2.0));
+        let placements = engine.solve(100.0, 50.0).unwrap();
+        let columns: Vec<(f32, f32)> = placements[0..3].iter()
+            .map(|p| (p.cell.x, p.cell.width))
+            .collect();
+        assert_eq!(columns, vec![(0.0, 33.5), (33.5, 33.5), (67.0, 33.0)]);
+        assert_eq!(placements[3].content.width, 10.5);
+
+        engine.with_pixel_snapping(None);
+        let placements = engine.solve(100.0, 50.0).unwrap();
+        assert_eq!(placements[3].content.width, 10.3);
+    }
+
+    #[test]
+    fn height_for_width_layout() {
+        // a hundred glyphs, eight wide and sixteen tall, wrapped to fit
+        fn wrap(width: f32) -> f32 {
+            (800.0 / width).ceil() * 16.0
+        }
+
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .preferred_size(Size{width: 100.0, height: 16.0}));
         engine.with_cell(CellProperties::new()
-                        .anchor_right()
                         .expand_horizontal()
                         .fill_horizontal()
-                        .preferred_size(Size{width: 64.0, height: 64.0}));
+                        .padding(Rectangle{top: 0.0, left: 4.0, bottom: 0.0, right: 4.0})
+                        .preferred_size(Size{width: 108.0, height: 16.0})
+                        .height_for_width(Box::new(wrap)));
+
+        // measured at its preferred width, less padding
+        assert_eq!(engine.get_size_grouping().preferred.height, 128.0);
+
+        let mut events: Vec<TraceEvent> = Vec::new();
+        let placements = engine.solve_traced(508.0, 300.0, &mut |e| events.push(e.clone())).unwrap();
+        assert!(events.contains(&TraceEvent::HeightForWidth{index: 1, width: 400.0, height: 32.0}));
+        assert_eq!(placements[1].cell, Bounds{x: 100.0, y: 0.0, width: 408.0, height: 32.0});
+        assert_eq!(placements[1].content, Bounds{x: 104.0, y: 0.0, width: 400.0, height: 32.0});
+
+        // narrower columns make for a taller row
+        let placements = engine.solve(308.0, 300.0).unwrap();
+        assert_eq!(placements[0].cell.height, 64.0);
+        assert_eq!(placements[1].content, Bounds{x: 104.0, y: 0.0, width: 200.0, height: 64.0});
+
+        // a column which only has a minimum width is measured at that width,
+        // whether the table is solved alone or nested in another
+        let mut inner = TableLayout::new();
+        inner.with_cell(CellProperties::new()
+                       .minimum_size(Size{width: 50.0, height: 0.0})
+                       .height_for_width(Box::new(wrap)));
+        let size = inner.get_size_grouping();
+        assert_eq!((size.preferred.width, size.preferred.height), (50.0, 256.0));
+        assert_eq!(inner.solve(50.0, 300.0).unwrap()[0].cell.height, 256.0);
+
+        let mut outer = TableLayout::new();
+        outer.with_cell(CellProperties::new()
+                       .table(inner));
+        let placements = outer.solve(100.0, 300.0).unwrap();
+        assert_eq!(placements[0].content, Bounds{x: 0.0, y: 0.0, width: 50.0, height: 256.0});
+    }
+
+    #[test]
+    fn item_layout() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        // stands in for a widget, which keeps wherever it was put
+        struct Label {
+            glyphs: usize,
+            placed: Rc<RefCell<Option<Bounds>>>,
+        }
+
+        impl LayoutItem for Label {
+            fn measure(&self) -> SizeGrouping {
+                SizeGrouping{
+                    minimum:   Size{width: 8.0, height: 16.0},
+                    preferred: Size{width: 8.0 * self.glyphs as f32, height: 16.0},
+                    maximum:   Size{width: 8.0 * self.glyphs as f32, height: 16.0},
+                }
+            }
+
+            fn height_for_width(&self, width: f32) -> Option<f32> {
+                Some((8.0 * self.glyphs as f32 / width).ceil() * 16.0)
+            }
+
+            fn place(&mut self, x: f32, y: f32, width: f32, height: f32) {
+                *self.placed.borrow_mut() = Some(Bounds{x, y, width, height});
+            }
+        }
+
+        let title = Rc::new(RefCell::new(None));
+        let body = Rc::new(RefCell::new(None));
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .minimum_size(Size{width: 32.0, height: 0.0})
+                        .item(Box::new(Label{glyphs: 4, placed: title.clone()})));
+        engine.with_cell(CellProperties::new()
+                        .fill_horizontal()
+                        .item(Box::new(Label{glyphs: 50, placed: body.clone()})));
+
+        // items are measured along with whatever sizes the cell sets
+        let size = engine.get_size_grouping();
+        assert_eq!(size.minimum.width, 40.0);
+        assert_eq!(size.preferred.width, 432.0);
+
+        // the body is squeezed to 168 wide, so wraps onto three lines
+        engine.impose(200.0, 100.0).unwrap();
+        assert_eq!(*title.borrow(), Some(Bounds{x: 0.0, y: 0.0, width: 32.0, height: 16.0}));
+        assert_eq!(*body.borrow(), Some(Bounds{x: 32.0, y: 0.0, width: 168.0, height: 48.0}));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_layout() {
+        use std::cell::Cell;
+        use std::rc::Rc;
+
+        let mut inner = TableLayout::new();
+        inner.with_cell(CellProperties::new()
+                       .id("icon")
+                       .preferred_size(Size{width: 16.0, height: 16.0}));
+
+        let mut engine = TableLayout::new();
+        engine.with_spacing(4.0, 4.0)
+            .with_column_defaults(1, CellProperties::new().expand_horizontal().fill_horizontal());
+        engine.with_cell(CellProperties::new()
+                        .table(inner));
+        engine.with_cell(CellProperties::new()
+                        .id("name")
+                        .anchor_center()
+                        .preferred_size(Size{width: 64.0, height: 16.0})
+                        .callback(Box::new(|_, _, _, _| {})));
         engine.with_row();
         engine.with_cell(CellProperties::new()
-                        .colspan(3)
-                        .expand_vertical()
-                        .anchor_bottom()
+                        .colspan(2)
+                        .padding(Rectangle{top: 2.0, left: 2.0, bottom: 2.0, right: 2.0})
+                        .minimum_size(Size{width: 32.0, height: 32.0}));
+
+        let json = serde_json::to_string(&engine).unwrap();
+        assert!(json.contains(r#""flags":["AnchorHorizontalCenter","AnchorVerticalCenter"]"#));
+        let mut copy: TableLayout = serde_json::from_str(&json).unwrap();
+        assert_eq!(copy.solve(200.0, 100.0).unwrap(), engine.solve(200.0, 100.0).unwrap());
+
+        // callbacks are not stored, but can be bound again by name
+        let placed = Rc::new(Cell::new(None));
+        let seen = placed.clone();
+        assert!(copy.bind("icon", Box::new(move |x, y, w, h| seen.set(Some((x, y, w, h))))));
+        assert!(!copy.bind("missing", Box::new(|_, _, _, _| {})));
+        copy.impose(200.0, 100.0).unwrap();
+        assert_eq!(placed.get(), Some((0.0, 0.0, 16.0, 16.0)));
+
+        // hand written cells may leave out whatever they do not need
+        let cell: CellProperties = serde_json::from_str(r#"{"flags": ["ExpandHorizontal"], "colspan": 3}"#).unwrap();
+        assert_eq!(cell.flags, CellFlags::ExpandHorizontal);
+        assert_eq!(cell.rowspan, 1);
+        assert!(serde_json::from_str::<CellFlags>(r#"["Sideways"]"#).is_err());
+
+        // whatever is written counts as set, so sizes need no other markings
+        let engine: TableLayout = serde_json::from_str(r#"{
+            "column_defaults": {"0": {"size": {"preferred": {"width": 10, "height": 30}}}},
+            "opcodes": [
+                {"Cell": {"size": {"preferred": {"width": 40}}}},
+                {"Cell": {"size": {"preferred": {"width": 40, "height": 20}}}}
+            ]
+        }"#).unwrap();
+        let placements = engine.solve(80.0, 30.0).unwrap();
+        assert_eq!(placements[0].content, Bounds{x: 0.0, y: 0.0, width: 40.0, height: 30.0});
+        assert_eq!(placements[1].content, Bounds{x: 40.0, y: 0.0, width: 40.0, height: 20.0});
+
+        // only what was set is written, including sizes set to their
+        // default values, and `specified` is worked out again when read
+        assert!(!json.contains("specified"));
+        let json = serde_json::to_string(&CellProperties::new()
+                                         .expand()
+                                         .unset(CellFlags::FillVertical)
+                                         .preferred_size(Size{width: 0.0, height: 0.0})).unwrap();
+        assert_eq!(json, r#"{"size":{"preferred":{"width":0.0,"height":0.0}},"flags":["ExpandHorizontal","ExpandVertical"],"unset":["FillVertical"]}"#);
+        let cell: CellProperties = serde_json::from_str(&json).unwrap();
+        assert_eq!(cell.specified, Specified::PreferredSize);
+        assert_eq!(cell.unset, CellFlags::FillVertical);
+    }
+
+    #[test]
+    fn macro_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .anchor_right()
+                        .preferred_size(Size{width: 40.0, height: 16.0}));
+        engine.with_cell(CellProperties::new()
+                        .expand_horizontal()
                         .fill_horizontal()
-                        .preferred_size(Size{width: 64.0, height: 64.0}));
-        b.iter(|| engine.impose(test::black_box(320.0), test::black_box(240.0)))
+                        .preferred_size(Size{width: 100.0, height: 16.0}));
+        engine.with_row();
+        engine.with_cell(CellProperties::new()
+                        .colspan(2)
+                        .anchor_center()
+                        .padding(Rectangle{top: 4.0, left: 4.0, bottom: 4.0, right: 4.0}));
+
+        let grid = table! {
+            [ { anchor_right, preferred_size(Size{width: 40.0, height: 16.0}) },
+              { expand_horizontal(), fill_horizontal, preferred_size(Size{width: 100.0, height: 16.0}), } ],
+            [ { colspan(2), anchor_center,
+                padding(Rectangle{top: 4.0, left: 4.0, bottom: 4.0, right: 4.0}) } ],
+        };
+        assert_eq!(grid.get_rows_cols(), engine.get_rows_cols());
+        assert_eq!(grid.row, engine.row);
+        assert_eq!(grid.solve(200.0, 100.0).unwrap(), engine.solve(200.0, 100.0).unwrap());
+
+        let empty: TableLayout = table! {};
+        assert!(empty.opcodes.is_empty());
+        let row = table! { [ {}, {} ] };
+        assert_eq!(row.get_rows_cols(), (1, 2));
     }
 }
 
@@ -0,0 +1,151 @@
+/// Builds a `TableLayout` laid out the way it will look. Each row is a
+/// list of cells in square brackets, and each cell is a list of
+/// `CellProperties` builder calls in braces; calls without arguments
+/// may leave off their parentheses.
+///
+/// ```
+/// #[macro_use]
+/// extern crate sktablelayout;
+/// use sktablelayout::*;
+///
+/// fn main() {
+///     let mut engine = table! {
+///         [ { preferred_size(Size{width: 64.0, height: 64.0}) },
+///           { expand, fill, preferred_size(Size{width: 64.0, height: 64.0}) } ],
+///         [ { colspan(2), anchor_center, callback(Box::new(|x, y, w, h| {
+///               println!("{} {} {} {}", x, y, w, h);
+///           })) } ],
+///     };
+///     engine.impose(320.0, 240.0).unwrap();
+/// }
+/// ```
+///
+/// The macro may also be called by its path, without `#[macro_use]`:
+///
+/// ```
+/// extern crate sktablelayout;
+///
+/// fn main() {
+///     let engine = sktablelayout::table! { [ {}, { expand } ] };
+///     assert_eq!(engine.get_rows_cols(), (1, 2));
+/// }
+/// ```
+///
+/// This expands into the same `with_cell` and `with_row` calls that
+/// would be written out by hand, so the table can be changed afterwards
+/// with the usual builder methods. Tables are made with
+/// `TableLayout::new`, and so use `f32` coordinates.
+///
+/// Cells must sit inside of a row:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate sktablelayout;
+/// use sktablelayout::*;
+///
+/// fn main() {
+///     let engine = table! { { expand } };
+/// }
+/// ```
+///
+/// Cells and rows are separated by commas:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate sktablelayout;
+/// use sktablelayout::*;
+///
+/// fn main() {
+///     let engine = table! { [ { expand } { fill } ] };
+/// }
+/// ```
+///
+/// Only a single trailing comma is allowed:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate sktablelayout;
+/// use sktablelayout::*;
+///
+/// fn main() {
+///     let engine = table! { [ { expand,, fill } ] };
+/// }
+/// ```
+///
+/// Properties must be builder calls, not bare values:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate sktablelayout;
+/// use sktablelayout::*;
+///
+/// fn main() {
+///     let engine = table! { [ { colspan: 2 } ] };
+/// }
+/// ```
+///
+/// And only builders which exist on `CellProperties` will do:
+///
+/// ```compile_fail
+/// #[macro_use]
+/// extern crate sktablelayout;
+/// use sktablelayout::*;
+///
+/// fn main() {
+///     let engine = table! { [ { sideways } ] };
+/// }
+/// ```
+#[macro_export]
+macro_rules! table {
+    () => {
+        $crate::TableLayout::new()
+    };
+
+    ( [ $($first:tt)* ] $( , [ $($rest:tt)* ] )* ) => {{
+        let mut table = $crate::TableLayout::new();
+        $crate::table!(@cells table $($first)*);
+        $(
+            table.with_row();
+            $crate::table!(@cells table $($rest)*);
+        )*
+        table
+    }};
+
+    // a single trailing comma after the last row
+    ( [ $($first:tt)* ] $( , [ $($rest:tt)* ] )* , ) => {
+        $crate::table!([ $($first)* ] $( , [ $($rest)* ] )*)
+    };
+
+    (@cells $table:ident $( { $($cell:tt)* } ),* ) => {
+        $(
+            $table.with_cell($crate::table!(@cell $crate::CellProperties::new(); $($cell)*));
+        )*
+    };
+
+    // a single trailing comma after the last cell of a row
+    (@cells $table:ident $( { $($cell:tt)* } ),* , ) => {
+        $crate::table!(@cells $table $( { $($cell)* } ),*)
+    };
+
+    // builder calls are chained onto the cell one at a time, and a
+    // single trailing comma after the last of them is left over
+    (@cell $cell:expr; ) => {
+        $cell
+    };
+
+    (@cell $cell:expr; $method:ident ( $($arg:expr),* ) ) => {
+        $cell.$method($($arg),*)
+    };
+
+    (@cell $cell:expr; $method:ident ) => {
+        $cell.$method()
+    };
+
+    (@cell $cell:expr; $method:ident ( $($arg:expr),* ) , $($rest:tt)* ) => {
+        $crate::table!(@cell $cell.$method($($arg),*); $($rest)*)
+    };
+
+    (@cell $cell:expr; $method:ident , $($rest:tt)* ) => {
+        $crate::table!(@cell $cell.$method(); $($rest)*)
+    };
+}
@@ -0,0 +1,407 @@
+//! Adds cells to a table from MigLayout style component constraints,
+//! such as `"growx, pushx, span 2, wrap, gap 10"`.
+//!
+//! Constraints are separated by commas, and their values by spaces. The
+//! ones understood here, and what they become, are:
+//!
+//! - `grow`, `growx`, `growy`: the component fills its cell (`fill`).
+//! - `push`, `pushx`, `pushy`: the cell's columns or rows take up extra
+//!   space (`expand`). An optional weight, where `100` is the default,
+//!   sets the expansion weight.
+//! - `span X [Y]`, `spanx X`, `spany Y`: columns and rows covered.
+//! - `wrap`: starts a new row after the cell. `newline`: before it.
+//! - `skip [N]`: leaves `N` empty cells (one if not given) before it.
+//! - `gap left [right] [top] [bottom]`, `gapx left [right]`,
+//!   `gapy top [bottom]`, `gapleft`, `gapright`, `gaptop`, `gapbottom`:
+//!   padding around the component.
+//! - `width` or `w`, `height` or `h`: either a preferred size, or a
+//!   `min:preferred:max` triple where any part may be left blank.
+//!   `wmin`, `wmax`, `hmin` and `hmax` set one bound on its own.
+//! - `align X [Y]` or `al`, `alignx` or `ax`, `aligny` or `ay`: anchors,
+//!   being `left`, `right`, `top`, `bottom` or `center`. A single `align`
+//!   value applies along whichever axis it makes sense for.
+//! - `sizegroup` or `sg`: uniform size. Every group name shares a single
+//!   group, as there is only one uniform group per table.
+//! - `id NAME`: names the cell for `TableLayout::bind`.
+//!
+//! Sizes are plain numbers, optionally followed by `px`.
+
+use dsl::ParseError;
+use {CellProperties, Scalar, Specified, TableLayout};
+
+/// Word within a constraint string, and the column it began at.
+struct Word<'a> {
+    column: usize,
+    text:   &'a str,
+}
+
+/// A constraint's name, followed by its values.
+struct Constraint<'a> {
+    column: usize,
+    name:   &'a str,
+    values: Vec<Word<'a>>,
+}
+
+impl<'a> Constraint<'a> {
+    fn error(&self, message: String) -> ParseError {
+        ParseError{line: 1, column: self.column, message}
+    }
+
+    /// Complains unless the constraint has between `min` and `max` values.
+    fn expect(&self, min: usize, max: usize) -> Result<(), ParseError> {
+        let n = self.values.len();
+        if n < min || n > max {
+            let wanted = match (min, max) {
+                (0, 0) => "no values".to_string(),
+                (a, b) if a == b => format!("{} value{}", a, if a == 1 { "" } else { "s" }),
+                (a, b) => format!("{} to {} values", a, b),
+            };
+            return Err(self.error(format!("`{}` takes {}, not {}", self.name, wanted, n)))
+        }
+        Ok(())
+    }
+}
+
+impl<'a> Word<'a> {
+    fn error(&self, message: String) -> ParseError {
+        ParseError{line: 1, column: self.column, message}
+    }
+
+    fn size<T: Scalar>(&self) -> Result<T, ParseError> {
+        let text = if self.text.ends_with("px") { &self.text[..self.text.len()-2] } else { self.text };
+        text.parse::<f64>()
+            .map(T::from_f64)
+            .map_err(|_| self.error(format!("expected a size in pixels, not `{}`", self.text)))
+    }
+
+    fn count(&self) -> Result<usize, ParseError> {
+        match self.text.parse::<usize>() {
+            Ok(n) if n > 0 => Ok(n),
+            _ => Err(self.error(format!("expected a whole number above zero, not `{}`", self.text))),
+        }
+    }
+
+    fn weight(&self) -> Result<f32, ParseError> {
+        self.text.parse::<f32>()
+            .map(|w| w / 100.0)
+            .map_err(|_| self.error(format!("expected a weight, not `{}`", self.text)))
+    }
+}
+
+/// Splits constraints on commas and their values on whitespace,
+/// remembering which column each began at.
+fn split(constraints: &str) -> Vec<Constraint<'_>> {
+    let mut out = Vec::new();
+    let mut column = 1;
+    for part in constraints.split(',') {
+        let mut words: Vec<Word> = Vec::new();
+        let mut start: Option<(usize, usize)> = None;
+        for (i, c) in part.char_indices() {
+            if c.is_whitespace() {
+                if let Some((b, col)) = start.take() {
+                    words.push(Word{column: col, text: &part[b..i]});
+                }
+            } else if start.is_none() {
+                start = Some((i, column));
+            }
+            column += 1;
+        }
+        if let Some((b, col)) = start {
+            words.push(Word{column: col, text: &part[b..]});
+        }
+        column += 1; // the comma
+
+        let mut words = words.into_iter();
+        if let Some(name) = words.next() {
+            out.push(Constraint{column: name.column, name: name.text, values: words.collect()});
+        }
+    }
+    out
+}
+
+/// Applies an `align` value along whichever axis it belongs to.
+fn align<T: Scalar>(cell: CellProperties<T>, word: &Word, horizontal: bool, vertical: bool) -> Result<CellProperties<T>, ParseError> {
+    Ok(match word.text {
+        "left" | "leading" if horizontal => cell.anchor_left(),
+        "right" | "trailing" if horizontal => cell.anchor_right(),
+        "top" if vertical => cell.anchor_top(),
+        "bottom" if vertical => cell.anchor_bottom(),
+        "center" if horizontal && vertical => cell.anchor_center(),
+        "center" if horizontal => cell.anchor_horizontal_center(),
+        "center" if vertical => cell.anchor_vertical_center(),
+        other => return Err(word.error(format!("cannot align to `{}` here", other))),
+    })
+}
+
+/// Which of a cell's sizes a constraint sets.
+#[derive(Clone, Copy)]
+enum Bound {
+    Minimum,
+    Preferred,
+    Maximum,
+}
+
+/// Sets the width (if `horizontal`) or height of one of `cell`'s sizes
+/// to `n`. Only that axis is marked as specified, so the other is still
+/// left to row, column and table defaults.
+fn along<T: Scalar>(cell: &mut CellProperties<T>, bound: Bound, horizontal: bool, n: T) {
+    let (size, width, height) = match bound {
+        Bound::Minimum => (&mut cell.size.minimum, Specified::MinimumWidth, Specified::MinimumHeight),
+        Bound::Preferred => (&mut cell.size.preferred, Specified::PreferredWidth, Specified::PreferredHeight),
+        Bound::Maximum => (&mut cell.size.maximum, Specified::MaximumWidth, Specified::MaximumHeight),
+    };
+    if horizontal {
+        size.width = n;
+        cell.specified |= width;
+    } else {
+        size.height = n;
+        cell.specified |= height;
+    }
+}
+
+/// Reads a `min:preferred:max` triple, or just a preferred size.
+fn bounded<T: Scalar>(word: &Word) -> Result<[Option<T>; 3], ParseError> {
+    let parts: Vec<&str> = word.text.split(':').collect();
+    let read = |text: &str| -> Result<Option<T>, ParseError> {
+        if text.is_empty() {
+            Ok(None)
+        } else {
+            Word{column: word.column, text}.size().map(Some)
+        }
+    };
+    match parts.len() {
+        1 => Ok([None, read(parts[0])?, None]),
+        3 => Ok([read(parts[0])?, read(parts[1])?, read(parts[2])?]),
+        _ => Err(word.error(format!("expected `size` or `min:preferred:max`, not `{}`", word.text))),
+    }
+}
+
+/// Adds `cell` to the table, after layering the MigLayout style
+/// `constraints` on top of it. Constraints which affect rows, such as
+/// `wrap`, are carried out on the table as well. Nothing is added if the
+/// constraints have a mistake in them; the error gives the column the
+/// mistake was found at, on line one.
+pub fn add<T: Scalar>(table: &mut TableLayout<T>, cell: CellProperties<T>, constraints: &str) -> Result<(), ParseError> {
+    let mut cell = cell;
+    let mut newline = false;
+    let mut wrap = false;
+    let mut skip = 0;
+
+    for c in split(constraints) {
+        let v = &c.values;
+        cell = match c.name {
+            "grow" => { c.expect(0, 0)?; cell.fill() }
+            "growx" => { c.expect(0, 0)?; cell.fill_horizontal() }
+            "growy" => { c.expect(0, 0)?; cell.fill_vertical() }
+            "push" | "pushx" | "pushy" => {
+                c.expect(0, 1)?;
+                let cell = match c.name {
+                    "push" => cell.expand(),
+                    "pushx" => cell.expand_horizontal(),
+                    _ => cell.expand_vertical(),
+                };
+                match v.first() {
+                    Some(w) => cell.expand_weight(w.weight()?),
+                    None => cell,
+                }
+            }
+            "span" => {
+                c.expect(1, 2)?;
+                let cell = cell.colspan(v[0].count()?);
+                match v.get(1) {
+                    Some(y) => cell.rowspan(y.count()?),
+                    None => cell,
+                }
+            }
+            "spanx" => { c.expect(1, 1)?; cell.colspan(v[0].count()?) }
+            "spany" => { c.expect(1, 1)?; cell.rowspan(v[0].count()?) }
+            "wrap" => { c.expect(0, 0)?; wrap = true; cell }
+            "newline" => { c.expect(0, 0)?; newline = true; cell }
+            "skip" => {
+                c.expect(0, 1)?;
+                skip += match v.first() {
+                    Some(n) => n.count()?,
+                    None => 1,
+                };
+                cell
+            }
+            "gap" | "gapx" | "gapy" | "gapleft" | "gapright" | "gaptop" | "gapbottom" => {
+                let mut padding = cell.padding.clone();
+                match c.name {
+                    "gap" => {
+                        c.expect(1, 4)?;
+                        padding.left = v[0].size()?;
+                        padding.right = match v.get(1) { Some(w) => w.size()?, None => padding.left };
+                        if let Some(w) = v.get(2) { padding.top = w.size()?; }
+                        if let Some(w) = v.get(3) { padding.bottom = w.size()?; }
+                    }
+                    "gapx" => {
+                        c.expect(1, 2)?;
+                        padding.left = v[0].size()?;
+                        padding.right = match v.get(1) { Some(w) => w.size()?, None => padding.left };
+                    }
+                    "gapy" => {
+                        c.expect(1, 2)?;
+                        padding.top = v[0].size()?;
+                        padding.bottom = match v.get(1) { Some(w) => w.size()?, None => padding.top };
+                    }
+                    side => {
+                        c.expect(1, 1)?;
+                        let n = v[0].size()?;
+                        match side {
+                            "gapleft" => padding.left = n,
+                            "gapright" => padding.right = n,
+                            "gaptop" => padding.top = n,
+                            _ => padding.bottom = n,
+                        }
+                    }
+                }
+                cell.padding(padding)
+            }
+            "width" | "w" | "height" | "h" => {
+                c.expect(1, 1)?;
+                let [min, pref, max] = bounded(&v[0])?;
+                let horizontal = c.name.starts_with('w');
+                let mut cell = cell;
+                for (bound, n) in [(Bound::Minimum, min), (Bound::Preferred, pref), (Bound::Maximum, max)].iter() {
+                    if let Some(n) = n {
+                        along(&mut cell, *bound, horizontal, *n);
+                    }
+                }
+                cell
+            }
+            "wmin" | "hmin" => {
+                c.expect(1, 1)?;
+                let mut cell = cell;
+                along(&mut cell, Bound::Minimum, c.name == "wmin", v[0].size()?);
+                cell
+            }
+            "wmax" | "hmax" => {
+                c.expect(1, 1)?;
+                let mut cell = cell;
+                along(&mut cell, Bound::Maximum, c.name == "wmax", v[0].size()?);
+                cell
+            }
+            "align" | "al" => {
+                c.expect(1, 2)?;
+                // one value may go either way; two are horizontal, then vertical
+                match v.get(1) {
+                    Some(y) => align(align(cell, &v[0], true, false)?, y, false, true)?,
+                    None => align(cell, &v[0], true, true)?,
+                }
+            }
+            "alignx" | "ax" => { c.expect(1, 1)?; align(cell, &v[0], true, false)? }
+            "aligny" | "ay" => { c.expect(1, 1)?; align(cell, &v[0], false, true)? }
+            "sizegroup" | "sg" => { c.expect(0, 1)?; cell.uniform() }
+            "id" => { c.expect(1, 1)?; cell.id(v[0].text) }
+            other => return Err(c.error(format!("unknown constraint `{}`", other))),
+        };
+    }
+
+    if newline {
+        table.with_row();
+    }
+    for _i in 0..skip {
+        table.with_cell(CellProperties::default());
+    }
+    table.with_cell(cell);
+    if wrap {
+        table.with_row();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use ::*;
+    use mig::*;
+
+    #[test]
+    fn mig_layout() {
+        let mut engine = TableLayout::new();
+        add(&mut engine, CellProperties::new(), "id name, w 40, h 16, align right center").unwrap();
+        add(&mut engine, CellProperties::new(), "growx, pushx 300, w 20:100:400px, gap 4 4 2, wrap").unwrap();
+        add(&mut engine, CellProperties::new(), "skip, ay bottom, sg, spanx 1").unwrap();
+        add(&mut engine, CellProperties::new(), "newline, span 2 1, gapy 8, hmin 8, wmax 64, sizegroup buttons").unwrap();
+
+        assert_eq!(engine.get_rows_cols(), (3, 2));
+        let cells: Vec<&CellProperties> = engine.opcodes.iter()
+            .filter_map(|op| match op { LayoutOp::Cell(cp) => Some(cp), LayoutOp::Row => None })
+            .collect();
+        assert_eq!(cells.len(), 5);
+
+        assert_eq!(cells[0].id.as_deref(), Some("name"));
+        assert_eq!(cells[0].flags, CellFlags::AnchorRight | CellFlags::AnchorVerticalCenter);
+        assert_eq!((cells[0].size.preferred.width, cells[0].size.preferred.height), (40.0, 16.0));
+
+        assert_eq!(cells[1].flags, CellFlags::FillHorizontal | CellFlags::ExpandHorizontal);
+        assert_eq!(cells[1].expand_weight, 3.0);
+        assert_eq!(cells[1].size.minimum.width, 20.0);
+        assert_eq!(cells[1].size.preferred.width, 100.0);
+        assert_eq!(cells[1].size.maximum.width, 400.0);
+        assert_eq!((cells[1].padding.left, cells[1].padding.right), (4.0, 4.0));
+        assert_eq!((cells[1].padding.top, cells[1].padding.bottom), (2.0, 0.0));
+
+        // skipped cells are left empty
+        assert_eq!(cells[2].flags, CellFlags::None);
+        assert_eq!(cells[3].flags, CellFlags::AnchorBottom | CellFlags::Uniform);
+
+        assert_eq!(cells[4].colspan, 2);
+        assert_eq!((cells[4].padding.top, cells[4].padding.bottom), (8.0, 8.0));
+        assert_eq!(cells[4].size.minimum.height, 8.0);
+        assert_eq!(cells[4].size.maximum.width, 64.0);
+        assert_eq!(cells[4].specified, Specified::Padding | Specified::MinimumHeight | Specified::MaximumWidth);
+
+        // the given cell keeps its callback
+        let mut engine = TableLayout::new();
+        add(&mut engine, CellProperties::new()
+            .callback(Box::new(|x, _, w, _| {
+                assert_eq!((x, w), (10.0, 80.0));
+            })), "grow, push, gap 10").unwrap();
+        engine.impose(100.0, 100.0).unwrap();
+    }
+
+    #[test]
+    fn mig_defaults() {
+        let mut engine = TableLayout::new();
+        engine.with_column_defaults(0, CellProperties::new()
+                                    .preferred_size(Size{width: 10.0, height: 30.0}));
+        engine.with_row_defaults(0, CellProperties::new()
+                                 .minimum_size(Size{width: 5.0, height: 5.0})
+                                 .maximum_size(Size{width: 50.0, height: 50.0}));
+
+        // each constraint only sets the axis it names, leaving the other
+        // to the row and column defaults
+        add(&mut engine, CellProperties::new(), "w 40, hmin 20, wmax 45, grow").unwrap();
+        let placements = engine.solve(100.0, 100.0).unwrap();
+        assert_eq!(placements[0].cell, Bounds{x: 0.0, y: 0.0, width: 40.0, height: 30.0});
+        assert_eq!(placements[0].content, Bounds{x: 0.0, y: 0.0, width: 40.0, height: 30.0});
+
+        engine.clear();
+        add(&mut engine, CellProperties::new(), "h 12").unwrap();
+        let placements = engine.solve(100.0, 100.0).unwrap();
+        assert_eq!(placements[0].cell, Bounds{x: 0.0, y: 0.0, width: 10.0, height: 12.0});
+    }
+
+    #[test]
+    fn mig_errors() {
+        let error = |constraints: &str| {
+            add(&mut TableLayout::<f32>::default(), CellProperties::default(), constraints).unwrap_err()
+        };
+
+        assert_eq!(error("grow, sideways"),
+                   ParseError{line: 1, column: 7, message: "unknown constraint `sideways`".to_string()});
+        assert_eq!(error("grow,  span 0"),
+                   ParseError{line: 1, column: 13, message: "expected a whole number above zero, not `0`".to_string()});
+        assert_eq!(error("span").message, "`span` takes 1 to 2 values, not 0");
+        assert_eq!(error("wrap 2").message, "`wrap` takes no values, not 1");
+        assert_eq!(error("w 10%").message, "expected a size in pixels, not `10%`");
+        assert_eq!(error("w 1:2").message, "expected `size` or `min:preferred:max`, not `1:2`");
+        assert_eq!(error("ax top").message, "cannot align to `top` here");
+
+        // mistakes leave the table alone
+        let mut engine = TableLayout::new();
+        assert!(add(&mut engine, CellProperties::new(), "newline, skip 2, bogus").is_err());
+        assert!(engine.opcodes.is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+//! Draws a solved table as an SVG image, to see what the engine decided
+//! without wiring the table into a real interface. The output is a
+//! plain string, so it can be written to a file from a test or pasted
+//! into a bug report.
+//!
+//! Every element carries a class naming what it shows:
+//!
+//! - `bounds`: the area the table was solved in, drawn in red and dashed
+//!   if the layout was over-constrained.
+//! - `column`, `row`: the tracks cells were placed on.
+//! - `cell`: the area given to each cell, padding included.
+//! - `padding`: the padding reserved around each cell's contents.
+//! - `content`: the fitted box handed to the cell's callback.
+//! - `anchor`: a dot on the edge, corner or center the contents are
+//!   anchored to.
+//!
+//! Cells are grouped with their index, row, column and name as a title.
+//! Nested tables are drawn inside of the cell holding them.
+
+use std::fmt::Write;
+use {anchor, larger, Bounds, CellFlags, LayoutOp, Scalar, TableLayout};
+
+const STYLE: &str = "\
+.bounds{fill:none;stroke:#000}\
+.overconstrained{stroke:#dc2626;stroke-dasharray:4 2}\
+.column{fill:#3b82f6;fill-opacity:0.06;stroke:#3b82f6;stroke-opacity:0.3}\
+.row{fill:#22c55e;fill-opacity:0.06;stroke:#22c55e;stroke-opacity:0.3}\
+.cell{fill:none;stroke:#555;stroke-dasharray:2 2}\
+.padding{fill:#f59e0b;fill-opacity:0.3;fill-rule:evenodd}\
+.content{fill:#3b82f6;fill-opacity:0.25;stroke:#1d4ed8}\
+.anchor{fill:#dc2626}";
+
+/// Solves `table` within the given width and height, and draws the
+/// result. Callbacks and layout items are not called. The image grows
+/// to show cells which overflow the table.
+pub fn render<T: Scalar>(table: &TableLayout<T>, width: T, height: T) -> String {
+    let bounds = Bounds{x: T::zero(), y: T::zero(), width, height};
+    let mut body = String::new();
+    let (right, bottom) = draw(&mut body, table, &bounds);
+
+    let mut out = String::new();
+    let _ = write!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">",
+                   w = larger(width, right), h = larger(height, bottom));
+    let _ = write!(out, "<style>{}</style>", STYLE);
+    out.push_str(&body);
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Draws `table` solved within `bounds`, returning how far to the right
+/// and bottom its cells reach.
+fn draw<T: Scalar>(out: &mut String, table: &TableLayout<T>, bounds: &Bounds<T>) -> (T, T) {
+    let arrangement = table.arrange(bounds.width, bounds.height, &mut |_| {});
+    let (mut right, mut bottom) = (bounds.x + bounds.width, bounds.y + bounds.height);
+
+    let _ = write!(out, "<g transform=\"translate({} {})\">", bounds.x, bounds.y);
+    if arrangement.overflows.is_empty() {
+        rect(out, "bounds", &Bounds{x: T::zero(), y: T::zero(), width: bounds.width, height: bounds.height});
+    } else {
+        rect(out, "bounds overconstrained", &Bounds{x: T::zero(), y: T::zero(), width: bounds.width, height: bounds.height});
+    }
+
+    // Tracks run from the first column (or row) to the end of the last.
+    let extent = |tracks: &[(T, T)]| match (tracks.first(), tracks.last()) {
+        (Some(first), Some(last)) => (first.0, last.0 + last.1 - first.0),
+        _ => (T::zero(), T::zero()),
+    };
+    let (x, width) = extent(&arrangement.columns);
+    let (y, height) = extent(&arrangement.rows);
+    for &(offset, size) in &arrangement.columns {
+        rect(out, "column", &Bounds{x: offset, y, width: size, height});
+    }
+    for &(offset, size) in &arrangement.rows {
+        rect(out, "row", &Bounds{x, y: offset, width, height: size});
+    }
+
+    let cells: Vec<_> = table.opcodes.iter()
+        .filter_map(|op| match op { LayoutOp::Cell(cp) => Some(cp), LayoutOp::Row => None })
+        .collect();
+
+    for (p, style) in arrangement.placements.iter().zip(&arrangement.styles) {
+        right = larger(right, bounds.x + p.cell.x + p.cell.width);
+        bottom = larger(bottom, bounds.y + p.cell.y + p.cell.height);
+
+        let cell = cells.get(p.index);
+        let _ = write!(out, "<g><title>cell {}", p.index);
+        if let Some(id) = cell.and_then(|cp| cp.id.as_ref()) {
+            let _ = write!(out, " [{}]", escape(id));
+        }
+        let _ = write!(out, ": row {}, column {}</title>", p.row, p.column);
+        rect(out, "cell", &p.cell);
+
+        // The padding is what lies between the cell and the area inside it.
+        let inner = Bounds{
+            x: p.cell.x + style.padding.left,
+            y: p.cell.y + style.padding.top,
+            width: larger(p.cell.width - style.padding.horizontal(), T::zero()),
+            height: larger(p.cell.height - style.padding.vertical(), T::zero()),
+        };
+        if style.padding.horizontal() > T::zero() || style.padding.vertical() > T::zero() {
+            let _ = write!(out, "<path class=\"padding\" d=\"M{} {}h{}v{}h{}z M{} {}h{}v{}h{}z\"/>",
+                           p.cell.x, p.cell.y, p.cell.width, p.cell.height, T::zero() - p.cell.width,
+                           inner.x, inner.y, inner.width, inner.height, T::zero() - inner.width);
+        }
+
+        rect(out, "content", &p.content);
+
+        // Anchoring a box of no size finds the point it sticks to.
+        let ax = anchor(inner.width, T::zero(), style.flags,
+                        CellFlags::AnchorLeft, CellFlags::AnchorRight, CellFlags::AnchorHorizontalCenter);
+        let ay = anchor(inner.height, T::zero(), style.flags,
+                        CellFlags::AnchorTop, CellFlags::AnchorBottom, CellFlags::AnchorVerticalCenter);
+        let _ = write!(out, "<circle class=\"anchor\" cx=\"{}\" cy=\"{}\" r=\"2\"/>", inner.x + ax, inner.y + ay);
+
+        if let Some(nested) = cell.and_then(|cp| cp.table.as_ref()) {
+            let (r, b) = draw(out, nested, &p.content);
+            right = larger(right, bounds.x + r);
+            bottom = larger(bottom, bounds.y + b);
+        }
+        out.push_str("</g>");
+    }
+
+    out.push_str("</g>");
+    (right, bottom)
+}
+
+fn rect<T: Scalar>(out: &mut String, class: &str, b: &Bounds<T>) {
+    let _ = write!(out, "<rect class=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"/>",
+                   class, b.x, b.y, b.width, b.height);
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use ::*;
+    use svg::*;
+
+    #[test]
+    fn rendered_layout() {
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .id("<icon>")
+                        .anchor_right()
+                        .preferred_size(Size{width: 16.0, height: 16.0}));
+        engine.with_cell(CellProperties::new()
+                        .expand()
+                        .anchor_center()
+                        .padding(Rectangle{top: 2.0, left: 2.0, bottom: 2.0, right: 2.0})
+                        .preferred_size(Size{width: 32.0, height: 16.0}));
+
+        let svg = render(&engine, 100.0, 40.0);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"100\" height=\"40\""));
+        assert!(svg.ends_with("</svg>\n"));
+        assert!(svg.contains("<rect class=\"bounds\" x=\"0\" y=\"0\" width=\"100\" height=\"40\"/>"));
+        assert_eq!(svg.matches("class=\"column\"").count(), 2);
+        assert_eq!(svg.matches("class=\"row\"").count(), 1);
+        assert!(svg.contains("<rect class=\"column\" x=\"16\" y=\"0\" width=\"84\" height=\"40\"/>"));
+        assert!(svg.contains("<title>cell 0 [&lt;icon&gt;]: row 0, column 0</title>"));
+
+        // only the second cell is padded
+        assert_eq!(svg.matches("class=\"padding\"").count(), 1);
+        assert!(svg.contains("<path class=\"padding\" d=\"M16 0h84v40h-84z M18 2h80v36h-80z\"/>"));
+        assert!(svg.contains("<rect class=\"content\" x=\"42\" y=\"12\" width=\"32\" height=\"16\"/>"));
+
+        // the icon sticks to the top right corner, the other to the middle
+        assert!(svg.contains("<circle class=\"anchor\" cx=\"16\" cy=\"0\" r=\"2\"/>"));
+        assert!(svg.contains("<circle class=\"anchor\" cx=\"58\" cy=\"20\" r=\"2\"/>"));
+    }
+
+    #[test]
+    fn rendered_overflow() {
+        let mut inner = TableLayout::new();
+        inner.with_cell(CellProperties::new()
+                        .preferred_size(Size{width: 8.0, height: 8.0}));
+
+        let mut engine = TableLayout::new();
+        engine.with_cell(CellProperties::new()
+                        .minimum_size(Size{width: 60.0, height: 10.0})
+                        .preferred_size(Size{width: 60.0, height: 10.0}));
+        engine.with_cell(CellProperties::new()
+                        .table(inner));
+
+        let svg = render(&engine, 40.0, 10.0);
+        assert!(svg.contains("<rect class=\"bounds overconstrained\""));
+        // the image grows to show the cell running past the table
+        assert!(svg.contains("width=\"60\" height=\"10\" viewBox=\"0 0 60 10\""));
+        // the nested table is drawn where its cell's contents were placed
+        assert_eq!(svg.matches("<g transform=").count(), 2);
+        assert!(svg.contains("<g transform=\"translate(60 0)\">"));
+        assert_eq!(svg.matches("class=\"content\"").count(), 3);
+    }
+}